@@ -5,14 +5,15 @@ use bevy::{
     render::texture::Image,
 };
 
-use crate::{client::sets::Connected, enums::GameState};
+use crate::enums::GameState;
 
 use self::{
-    resources::{AssetLoading, TextAsset, TextLoader},
+    resources::{AssetConfigPath, AssetLoading, TextAsset, TextLoader},
     systems::{asset_config_loader_sytem, asset_loader_state_system, asset_loader_system},
 };
 
-pub mod enums;
+pub mod params;
+pub mod registry;
 pub mod resources;
 mod systems;
 
@@ -28,14 +29,13 @@ impl Plugin for AssetPlugin {
         app.init_asset_loader::<TextLoader>();
 
         app.insert_resource(AssetLoading::default());
+        app.init_resource::<AssetConfigPath>();
 
         // Clients
         app.add_systems(Startup, asset_config_loader_sytem);
         app.add_systems(
             Update,
-            asset_loader_system
-                .run_if(in_state(GameState::Loading))
-                .in_set(Connected),
+            asset_loader_system.run_if(in_state(GameState::Loading)),
         );
         app.add_systems(Update, asset_loader_state_system);
     }
@@ -0,0 +1,95 @@
+use bevy::{ecs::system::SystemParam, prelude::Res, sprite::TextureAtlas};
+
+use crate::networking::models::Handshake;
+
+use super::{
+    registry::{EquipmentName, RegistryError, RegistryTable, SpriteName},
+    resources::{
+        AnimationConfig, AssetConfigHash, AssetHandler, AssetRegistry, AssetsConfig,
+        EquipmentStatsConfig, HitboxConfig,
+    },
+};
+
+/// Looks up loaded sprites by name, or by the id they are sent over the network with
+#[derive(SystemParam)]
+pub struct SpriteAssets<'w> {
+    asset_handler: Res<'w, AssetHandler>,
+    asset_registry: Res<'w, AssetRegistry>,
+}
+
+impl SpriteAssets<'_> {
+    pub fn get(
+        &self,
+        name: &SpriteName,
+    ) -> Result<&(TextureAtlas, Vec<AnimationConfig>, Option<HitboxConfig>), RegistryError> {
+        self.asset_handler
+            .textures
+            .get(name)
+            .ok_or_else(|| RegistryError::UnknownName {
+                kind: "sprite",
+                name: name.to_string(),
+            })
+    }
+
+    /// The id a sprite is sent over the network with
+    pub fn id(&self, name: &SpriteName) -> Result<u16, RegistryError> {
+        self.asset_registry.sprites.id(name)
+    }
+
+    /// The sprite of an id received over the network
+    pub fn get_by_id(
+        &self,
+        id: u16,
+    ) -> Result<&(TextureAtlas, Vec<AnimationConfig>, Option<HitboxConfig>), RegistryError> {
+        self.get(self.asset_registry.sprites.name(id)?)
+    }
+}
+
+/// Looks up equipment stats by name, or by the id they are sent over the network with
+#[derive(SystemParam)]
+pub struct EquipmentAssets<'w> {
+    asset_config: Res<'w, AssetsConfig>,
+    asset_registry: Res<'w, AssetRegistry>,
+}
+
+impl EquipmentAssets<'_> {
+    pub fn get(&self, name: &EquipmentName) -> Result<&EquipmentStatsConfig, RegistryError> {
+        self.asset_config
+            .stats
+            .equipment
+            .get(name)
+            .ok_or_else(|| RegistryError::UnknownName {
+                kind: "equipment",
+                name: name.to_string(),
+            })
+    }
+
+    /// The id an equipment is sent over the network with
+    pub fn id(&self, name: &EquipmentName) -> Result<u16, RegistryError> {
+        self.asset_registry.equipment.id(name)
+    }
+
+    /// The stats of an equipment id received over the network
+    pub fn get_by_id(&self, id: u16) -> Result<&EquipmentStatsConfig, RegistryError> {
+        self.get(self.asset_registry.equipment.name(id)?)
+    }
+}
+
+/// What peers have to agree on about the loaded assets when connecting
+#[derive(SystemParam)]
+pub struct AssetManifest<'w> {
+    asset_config_hash: Res<'w, AssetConfigHash>,
+    asset_registry: Res<'w, AssetRegistry>,
+}
+
+impl AssetManifest<'_> {
+    /// The handshake of this build with the loaded asset config
+    pub fn handshake(&self) -> Handshake {
+        Handshake::current(**self.asset_config_hash)
+    }
+
+    /// The id tables accepted clients take over
+    pub fn registry_table(&self) -> RegistryTable {
+        self.asset_registry.table()
+    }
+}
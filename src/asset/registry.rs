@@ -0,0 +1,170 @@
+use std::{collections::HashMap, fmt, hash::Hash};
+
+use bevy::prelude::Deref;
+use serde::{Deserialize, Serialize};
+
+/**
+ * Sprite Name
+ *
+ * Key of a sprite asset in the asset config
+ */
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Deref)]
+#[serde(transparent)]
+pub struct SpriteName(pub String);
+
+impl From<&str> for SpriteName {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl fmt::Display for SpriteName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/**
+ * Equipment Name
+ *
+ * Key of an equipment in the stats of the asset config
+ */
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Deref)]
+#[serde(transparent)]
+pub struct EquipmentName(pub String);
+
+impl From<&str> for EquipmentName {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl fmt::Display for EquipmentName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/**
+ * Card Name
+ *
+ * Key of a card in the cards of the asset config
+ */
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Deref)]
+#[serde(transparent)]
+pub struct CardName(pub String);
+
+impl From<&str> for CardName {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl fmt::Display for CardName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegistryError {
+    UnknownName { kind: &'static str, name: String },
+    UnknownId { kind: &'static str, id: u16 },
+    Full { kind: &'static str },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::UnknownName { kind, name } => write!(f, "unknown {} {:?}", kind, name),
+            RegistryError::UnknownId { kind, id } => write!(f, "unknown {} id {}", kind, id),
+            RegistryError::Full { kind } => write!(f, "too many {} entries", kind),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// Maps the names of one kind of asset to compact ids sent over the network,
+/// ids are indices into the name table
+#[derive(Debug, Clone)]
+pub struct Registry<N> {
+    kind: &'static str,
+    names: Vec<N>,
+    ids: HashMap<N, u16>,
+}
+
+impl<N: Clone + Eq + Hash + Ord + fmt::Display> Registry<N> {
+    /// Builds the registry from the names found in the asset config,
+    /// sorted so every peer loading the same config gets the same ids
+    pub fn new(
+        kind: &'static str,
+        names: impl IntoIterator<Item = N>,
+    ) -> Result<Self, RegistryError> {
+        let mut names: Vec<N> = names.into_iter().collect();
+        names.sort();
+        names.dedup();
+
+        Self::from_table(kind, names)
+    }
+
+    /// Builds the registry from a name table, where each name's id is its index
+    pub fn from_table(kind: &'static str, names: Vec<N>) -> Result<Self, RegistryError> {
+        if names.len() > u16::MAX as usize + 1 {
+            return Err(RegistryError::Full { kind });
+        }
+
+        let ids = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (name.clone(), id as u16))
+            .collect();
+
+        Ok(Self { kind, names, ids })
+    }
+
+    /// Takes over the ids of another peer's table, every name
+    /// in it must be known to this registry
+    pub fn adopt(&mut self, names: Vec<N>) -> Result<(), RegistryError> {
+        if let Some(name) = names.iter().find(|name| !self.ids.contains_key(*name)) {
+            return Err(RegistryError::UnknownName {
+                kind: self.kind,
+                name: name.to_string(),
+            });
+        }
+
+        *self = Self::from_table(self.kind, names)?;
+        Ok(())
+    }
+
+    pub fn id(&self, name: &N) -> Result<u16, RegistryError> {
+        self.ids
+            .get(name)
+            .copied()
+            .ok_or_else(|| RegistryError::UnknownName {
+                kind: self.kind,
+                name: name.to_string(),
+            })
+    }
+
+    pub fn name(&self, id: u16) -> Result<&N, RegistryError> {
+        self.names.get(id as usize).ok_or(RegistryError::UnknownId {
+            kind: self.kind,
+            id,
+        })
+    }
+
+    /// The names ordered by id, as sent to other peers
+    pub fn table(&self) -> Vec<N> {
+        self.names.clone()
+    }
+}
+
+/// Serializable struct
+/// the id tables of every registry, sent by the server when accepting a client
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RegistryTable {
+    pub sprites: Vec<SpriteName>,
+    pub equipment: Vec<EquipmentName>,
+    pub cards: Vec<CardName>,
+}
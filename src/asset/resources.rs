@@ -2,42 +2,493 @@ use std::collections::HashMap;
 
 use bevy::{
     asset::{AssetLoader, AsyncReadExt, LoadContext},
-    prelude::{Asset, Deref, Handle, Resource},
+    prelude::{Asset, Deref, Handle, Resource, Vec3},
     reflect::{TypePath, TypeUuid},
     sprite::TextureAtlas,
     utils::BoxedFuture,
 };
 use serde::{Deserialize, Serialize};
 
-use crate::enums::CollisionGroups;
 use crate::{
     animation::components::AnimationName,
-    asset::enums::{Equipment, Sprites},
+    asset::registry::{
+        CardName, EquipmentName, Registry, RegistryError, RegistryTable, SpriteName,
+    },
+    deck::keyword::components::Keyword,
+    enums::CollisionGroups,
+    networking::models::WORLD_EXTENT,
+    player::components::Team,
+    stats::components::{StackingPolicy, StatusKind},
 };
 
 #[derive(Resource, Default, Deref)]
 pub struct AssetLoading(pub u32);
 
+/// Path of the asset config, relative to the assets folder
+#[derive(Resource, Debug, Clone, Deref)]
+pub struct AssetConfigPath(pub String);
+
+impl Default for AssetConfigPath {
+    fn default() -> Self {
+        Self("assets.json".to_string())
+    }
+}
+
+/// Hash of the raw asset config, compared in the handshake so
+/// clients and the server agree on equipment and sprite stats
+#[derive(Resource, Debug, Default, Clone, Copy, Deref)]
+pub struct AssetConfigHash(pub u64);
+
 #[derive(Resource, Default)]
 pub struct AssetHandler {
-    pub textures: HashMap<Sprites, (TextureAtlas, Vec<AnimationConfig>, Option<HitboxConfig>)>,
+    pub textures: HashMap<SpriteName, (TextureAtlas, Vec<AnimationConfig>, Option<HitboxConfig>)>,
+}
+
+/// Ids of the sprites and equipment named in the asset config,
+/// clients take over the server's ids when they connect
+#[derive(Resource, Debug, Clone)]
+pub struct AssetRegistry {
+    pub sprites: Registry<SpriteName>,
+    pub equipment: Registry<EquipmentName>,
+    pub cards: Registry<CardName>,
+}
+
+impl AssetRegistry {
+    /// Builds the registries from the asset config, checking that
+    /// everything the config refers to by name is defined in it
+    pub fn new(config: &AssetsConfig) -> Result<Self, RegistryError> {
+        let sprites = Registry::new("sprite", config.sprites.textures.keys().cloned())?;
+        let equipment = Registry::new("equipment", config.stats.equipment.keys().cloned())?;
+        let cards = Registry::new("card", config.cards.keys().cloned())?;
+
+        let sprite_references = config
+            .stats
+            .equipment
+            .values()
+            .flat_map(|stats| [&stats.sprite, &stats.projectile_type])
+            .chain([&config.sprites.character]);
+        for sprite in sprite_references {
+            sprites.id(sprite)?;
+        }
+        let equipment_references = config
+            .cards
+            .values()
+            .filter_map(|card| match &card.effect {
+                CardEffect::Equipment(equipment) => Some(equipment),
+                CardEffect::Keywords(_) => None,
+            })
+            .chain(&config.inventory.loadout);
+        for equipment_reference in equipment_references {
+            equipment.id(equipment_reference)?;
+        }
+        for card in &config.deck.cards {
+            cards.id(card)?;
+        }
+
+        Ok(Self {
+            sprites,
+            equipment,
+            cards,
+        })
+    }
+
+    /// The id tables sent to connecting clients
+    pub fn table(&self) -> RegistryTable {
+        RegistryTable {
+            sprites: self.sprites.table(),
+            equipment: self.equipment.table(),
+            cards: self.cards.table(),
+        }
+    }
+
+    /// Takes over the server's ids, failing when the server knows
+    /// assets missing from this client's config
+    pub fn adopt(&mut self, table: RegistryTable) -> Result<(), RegistryError> {
+        self.sprites.adopt(table.sprites)?;
+        self.equipment.adopt(table.equipment)?;
+        self.cards.adopt(table.cards)
+    }
 }
 
 #[derive(Resource, Serialize, Deserialize)]
 pub struct AssetsConfig {
     pub sprites: SpritesConfig,
     pub stats: StatsConfig,
+    #[serde(default)]
+    pub map: MapConfig,
+    #[serde(default, rename = "match")]
+    pub match_rules: MatchConfig,
+    #[serde(default)]
+    pub teams: TeamConfig,
+    #[serde(default)]
+    pub inventory: InventoryConfig,
+    #[serde(default)]
+    pub cards: HashMap<CardName, CardConfig>,
+    #[serde(default)]
+    pub deck: DeckConfig,
+    #[serde(default)]
+    pub status: StatusConfig,
+}
+
+// MAP CONFIG
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct MapConfig {
+    /// Where the players of each team spawn, one list per team and one
+    /// spawn point picked per spawn, free-for-all players use all of them
+    pub spawn_points: Vec<Vec<[f32; 2]>>,
+    /// Seconds a dead player waits before respawning
+    pub respawn_delay: f32,
+    /// Seconds a respawned player can't be damaged for
+    pub spawn_protection: f32,
+}
+
+impl Default for MapConfig {
+    fn default() -> Self {
+        Self {
+            spawn_points: Vec::new(),
+            respawn_delay: 3.0,
+            spawn_protection: 2.0,
+        }
+    }
+}
+
+impl MapConfig {
+    /// Checks that every spawn point lies within the world
+    /// a quantized position can describe
+    pub fn validate(&self) -> Result<(), String> {
+        match self
+            .spawn_points
+            .iter()
+            .flatten()
+            .find(|point| point.iter().any(|value| value.abs() > WORLD_EXTENT))
+        {
+            Some(point) => Err(format!(
+                "spawn point {:?} lies outside the world extent of {}",
+                point, WORLD_EXTENT
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// One of the team's spawn points picked by the seed,
+    /// the origin when the team has none
+    pub fn spawn_point(&self, team: Team, seed: u64) -> Vec3 {
+        let spawn_points: Vec<[f32; 2]> = match team {
+            Team::Spectator => Vec::new(),
+            Team::Index(index) => self
+                .spawn_points
+                .get(index as usize)
+                .cloned()
+                .unwrap_or_default(),
+            Team::FreeForAll => self.spawn_points.concat(),
+        };
+
+        if spawn_points.is_empty() {
+            return Vec3::ZERO;
+        }
+
+        let [x, y] = spawn_points[(seed % spawn_points.len() as u64) as usize];
+        Vec3::new(x, y, 0.0)
+    }
+}
+
+// TEAM CONFIG
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TeamMode {
+    /// Players are split into this many teams
+    Teams(u8),
+    FreeForAll,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct TeamConfig {
+    pub mode: TeamMode,
+    /// How many more players a team may have than the smallest one before no one else may join it
+    pub max_imbalance: usize,
+    /// Seconds a player has to wait between team changes
+    pub change_cooldown: f32,
+    pub allow_spectators: bool,
+}
+
+impl Default for TeamConfig {
+    fn default() -> Self {
+        Self {
+            mode: TeamMode::Teams(2),
+            max_imbalance: 1,
+            change_cooldown: 10.0,
+            allow_spectators: true,
+        }
+    }
+}
+
+impl TeamConfig {
+    /// The teams players can play on
+    pub fn teams(&self) -> Vec<Team> {
+        match self.mode {
+            TeamMode::Teams(count) => (0..count.clamp(1, Team::MAX)).map(Team::Index).collect(),
+            TeamMode::FreeForAll => vec![Team::FreeForAll],
+        }
+    }
+
+    /// The team with the fewest players, the first one on a tie
+    pub fn auto_assign(&self, counts: &HashMap<Team, usize>) -> Team {
+        self.teams()
+            .into_iter()
+            .min_by_key(|team| counts.get(team).copied().unwrap_or_default())
+            .unwrap_or(Team::FreeForAll)
+    }
+
+    /// The team a player asking to join a team ends up on,
+    /// any playing team means free-for-all in a free-for-all match
+    pub fn requested(&self, team: Team) -> Team {
+        match (self.mode, team) {
+            (TeamMode::FreeForAll, Team::Index(_)) => Team::FreeForAll,
+            _ => team,
+        }
+    }
+
+    /// Why a player on a team can't switch to another one, `None` when it can
+    pub fn join_error(
+        &self,
+        from: Team,
+        to: Team,
+        counts: &HashMap<Team, usize>,
+    ) -> Option<String> {
+        if from == to {
+            return Some(format!("You are already on {}", to));
+        }
+
+        if to == Team::Spectator {
+            return (!self.allow_spectators).then(|| "Spectating is disabled".to_string());
+        }
+
+        let teams = self.teams();
+        if !teams.contains(&to) {
+            return Some(format!("{} is not playing in this match", to));
+        }
+
+        // counted as if the player had already left their team
+        let count = |team: &Team| {
+            let count = counts.get(team).copied().unwrap_or_default();
+            if *team == from {
+                count.saturating_sub(1)
+            } else {
+                count
+            }
+        };
+        let smallest = teams.iter().map(count).min().unwrap_or_default();
+        if count(&to) + 1 > smallest + self.max_imbalance.max(1) {
+            return Some(format!("{} has too many players", to));
+        }
+
+        None
+    }
+}
+
+// MATCH CONFIG
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct MatchConfig {
+    /// Seconds of warmup before the first round, once enough players joined
+    pub warmup: f32,
+    /// Players needed to start a match
+    pub min_players: usize,
+    /// Seconds a round lasts, the team with the most kills wins when it runs out
+    pub round_time: f32,
+    /// Kills a team needs to win a round, 0 for no limit
+    pub score_limit: u32,
+    /// Whether dead players wait for the next round instead of respawning,
+    /// a round then also ends when a single team is left standing
+    pub elimination: bool,
+    /// Rounds a team needs to win the match
+    pub rounds_to_win: u32,
+    /// Seconds between the end of a round and the next one
+    pub round_end_time: f32,
+    /// Seconds between the end of a match and the next warmup
+    pub match_end_time: f32,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            warmup: 10.0,
+            min_players: 2,
+            round_time: 120.0,
+            score_limit: 10,
+            elimination: false,
+            rounds_to_win: 3,
+            round_end_time: 5.0,
+            match_end_time: 10.0,
+        }
+    }
+}
+
+// INVENTORY CONFIG
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct InventoryConfig {
+    /// The equipment players spawn with, one per slot, the first one in hand
+    pub loadout: Vec<EquipmentName>,
+    /// Seconds it takes to switch to another slot, nothing can be fired meanwhile
+    pub swap_delay: f32,
+}
+
+impl Default for InventoryConfig {
+    fn default() -> Self {
+        Self {
+            loadout: vec!["AK47".into()],
+            swap_delay: 0.5,
+        }
+    }
+}
+
+// CARD CONFIG
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CardConfig {
+    pub name: CardName,
+    pub effect: CardEffect,
+    #[serde(default)]
+    pub target: CardTarget,
+    /// How far away the target may be, 0 for no limit
+    #[serde(default)]
+    pub range: f32,
+}
+
+/// What playing a card does
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum CardEffect {
+    /// Replaces the equipment in hand
+    Equipment(EquipmentName),
+    /// Applies the keywords to the target
+    Keywords(Vec<Keyword>),
+}
+
+/// Who a card can be played on
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CardTarget {
+    /// Only the player playing it
+    #[default]
+    Own,
+    /// The player playing it or a teammate
+    Ally,
+    /// A player of another team, or anyone else in free-for-all
+    Enemy,
+}
+
+impl CardTarget {
+    /// Whether a player of `team` may play the card on a player of `target_team`,
+    /// `own` when they target themselves
+    pub fn allows(&self, team: Team, target_team: Team, own: bool) -> bool {
+        match self {
+            CardTarget::Own => own,
+            CardTarget::Ally => own || team.ally_mask() & target_team.layer() != 0,
+            CardTarget::Enemy => !own && team.enemy_mask() & target_team.layer() != 0,
+        }
+    }
+}
+
+// DECK CONFIG
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct DeckConfig {
+    /// The cards every player's deck starts with, listed once per copy
+    pub cards: Vec<CardName>,
+    /// The most cards a hand holds
+    pub hand_size: usize,
+    /// Seconds between drawing cards while the hand isn't full
+    pub draw_interval: f32,
+}
+
+impl Default for DeckConfig {
+    fn default() -> Self {
+        Self {
+            cards: Vec::new(),
+            hand_size: 3,
+            draw_interval: 5.0,
+        }
+    }
+}
+
+// STATUS CONFIG
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct StatusConfig {
+    pub slowed: StatusEffectConfig,
+    pub hasted: StatusEffectConfig,
+    pub burning: StatusEffectConfig,
+    pub stunned: StatusEffectConfig,
+    pub invulnerable: StatusEffectConfig,
+}
+
+impl StatusConfig {
+    pub fn effect(&self, kind: StatusKind) -> &StatusEffectConfig {
+        match kind {
+            StatusKind::Slowed => &self.slowed,
+            StatusKind::Hasted => &self.hasted,
+            StatusKind::Burning => &self.burning,
+            StatusKind::Stunned => &self.stunned,
+            StatusKind::Invulnerable => &self.invulnerable,
+        }
+    }
+}
+
+impl Default for StatusConfig {
+    fn default() -> Self {
+        Self {
+            slowed: StatusEffectConfig::default(),
+            hasted: StatusEffectConfig::default(),
+            burning: StatusEffectConfig {
+                stacking: StackingPolicy::StackIntensity,
+                max_stacks: 3,
+                tick_interval: 1.0,
+            },
+            stunned: StatusEffectConfig::default(),
+            invulnerable: StatusEffectConfig::default(),
+        }
+    }
+}
+
+/// How the effects of one status kind stack and tick
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct StatusEffectConfig {
+    pub stacking: StackingPolicy,
+    /// The most stacks, or side by side effects, of the kind
+    pub max_stacks: u32,
+    /// Seconds between the effect's ticks, 0 for an effect that never ticks
+    pub tick_interval: f32,
+}
+
+impl Default for StatusEffectConfig {
+    fn default() -> Self {
+        Self {
+            stacking: StackingPolicy::Refresh,
+            max_stacks: 1,
+            tick_interval: 0.0,
+        }
+    }
 }
 
 // SPRITE CONFIG
 #[derive(Serialize, Deserialize)]
 pub struct SpritesConfig {
-    pub textures: HashMap<Sprites, SpriteConfig>,
+    /// The sprite players are drawn with
+    #[serde(default = "SpritesConfig::default_character")]
+    pub character: SpriteName,
+    pub textures: HashMap<SpriteName, SpriteConfig>,
+}
+
+impl SpritesConfig {
+    fn default_character() -> SpriteName {
+        "Skeleton".into()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SpriteConfig {
-    pub name: Sprites,
+    pub name: SpriteName,
     pub path: String,
     pub width: f32,
     pub height: f32,
@@ -109,23 +560,28 @@ impl AssetLoader for TextLoader {
 // STATS CONFIG
 #[derive(Serialize, Deserialize)]
 pub struct StatsConfig {
-    pub equipment: HashMap<Equipment, EquipmentStatsConfig>,
+    pub equipment: HashMap<EquipmentName, EquipmentStatsConfig>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct EquipmentStatsConfig {
-    pub name: Equipment,
+    pub name: EquipmentName,
+    /// The sprite shown while the equipment is in hand
+    pub sprite: SpriteName,
     pub magazine: u32,
     pub max_magazine: u32,
     pub fire_rate: f32,
     pub reload_time: f32,
     pub damage: u32,
     pub spray: f32,
-    pub projectile_type: Sprites,
+    pub projectile_type: SpriteName,
     pub projectile_speed: f32,
     pub projectile_size: f32,
     pub projectile_per_shot: u32,
     pub range: u32,
     pub layers: Vec<CollisionGroups>,
     pub masks: Vec<CollisionGroups>,
+    /// Keywords every projectile applies on a hit, besides its damage
+    #[serde(default)]
+    pub keywords: Vec<Keyword>,
 }
@@ -9,12 +9,19 @@ use bevy::{
     sprite::TextureAtlas,
 };
 
-use super::resources::{AssetConfigTextHandler, AssetHandler, AssetsConfig, TextAsset};
-use crate::enums::GameState;
+use super::resources::{
+    AssetConfigHash, AssetConfigPath, AssetConfigTextHandler, AssetHandler, AssetRegistry,
+    AssetsConfig, TextAsset,
+};
+use crate::{enums::GameState, math::hash_bytes};
 
-pub fn asset_config_loader_sytem(asset_server: Res<AssetServer>, mut commands: Commands) {
+pub fn asset_config_loader_sytem(
+    asset_server: Res<AssetServer>,
+    asset_config_path: Res<AssetConfigPath>,
+    mut commands: Commands,
+) {
     // load assets into asset handler
-    let asset_config_handle: Handle<TextAsset> = asset_server.load("assets.json");
+    let asset_config_handle: Handle<TextAsset> = asset_server.load(asset_config_path.0.clone());
 
     // store handlers into resource indvidually
     commands.insert_resource(AssetConfigTextHandler {
@@ -32,6 +39,12 @@ pub fn asset_loader_system(
     if let Some(config_str) = text_assets.get(&asset_config.handle) {
         let asset_config: AssetsConfig =
             serde_json::from_str(&config_str.0).expect("Could not parse the asset config.");
+        let asset_registry = AssetRegistry::new(&asset_config)
+            .unwrap_or_else(|error| panic!("Invalid asset config: {}.", error));
+        asset_config
+            .map
+            .validate()
+            .unwrap_or_else(|error| panic!("Invalid asset config: {}.", error));
 
         let mut character_handles = HashMap::new();
 
@@ -65,8 +78,16 @@ pub fn asset_loader_system(
         };
 
         commands.insert_resource(asset_handler);
+        commands.insert_resource(asset_registry);
         commands.insert_resource(asset_config);
-        state.set(GameState::Gameloop);
+        commands.insert_resource(AssetConfigHash(hash_bytes(config_str.0.as_bytes())));
+
+        // clients connect to the server once their assets are loaded
+        if cfg!(feature = "client") {
+            state.set(GameState::Connecting);
+        } else {
+            state.set(GameState::Gameloop);
+        }
     }
 }
 
@@ -1,3 +1,5 @@
+use std::process;
+
 use bevy::app::App;
 use bevy::DefaultPlugins;
 use bevy_2d_collisions::CollisionsPlugin;
@@ -5,12 +7,36 @@ use bevy_health_bar::ProgressBarPlugin;
 use bevy_renet::{transport::NetcodeClientPlugin, RenetClientPlugin};
 
 use utils::{
-    animation::AnimationPlugin, asset::AssetPlugin as InternalAssetPlugin, client::ClientPlugin,
-    deck::DeckPlugin, enums::GameState, input::InputPlugin, physics::PhysicsPlugin,
-    player::PlayerPlugin, stats::StatsPlugin, ui::UiPlugin,
+    animation::AnimationPlugin,
+    asset::AssetPlugin as InternalAssetPlugin,
+    client::{settings::ConnectionSettings, ClientPlugin},
+    deck::DeckPlugin,
+    enums::GameState,
+    input::InputPlugin,
+    networking::NetworkingPlugin,
+    physics::PhysicsPlugin,
+    player::PlayerPlugin,
+    round::RoundPlugin,
+    score::ScorePlugin,
+    settings::SettingsError,
+    stats::StatsPlugin,
+    team::TeamPlugin,
+    ui::UiPlugin,
 };
 
 fn main() {
+    let connection_settings = match ConnectionSettings::from_args(std::env::args().skip(1)) {
+        Ok(connection_settings) => connection_settings,
+        Err(SettingsError::Help) => {
+            println!("{}", ConnectionSettings::usage());
+            return;
+        }
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, ConnectionSettings::usage());
+            process::exit(2);
+        }
+    };
+
     let mut app = App::new();
 
     app.add_plugins((
@@ -18,6 +44,7 @@ fn main() {
         RenetClientPlugin,
         NetcodeClientPlugin,
         ClientPlugin,
+        NetworkingPlugin,
         InternalAssetPlugin,
         InputPlugin,
         AnimationPlugin,
@@ -25,11 +52,12 @@ fn main() {
         StatsPlugin,
         ProgressBarPlugin,
         CollisionsPlugin,
-        PlayerPlugin,
+        (PlayerPlugin, ScorePlugin, RoundPlugin, TeamPlugin),
         DeckPlugin,
         UiPlugin,
     ));
 
+    app.insert_resource(connection_settings);
     app.add_state::<GameState>();
 
     app.run();
@@ -1,23 +1,70 @@
-use std::time::Duration;
+use std::{process, time::Duration};
 
 use bevy::{app::ScheduleRunnerPlugin, prelude::*};
 use bevy_2d_collisions::CollisionsPlugin;
 
-use bevy_renet::{transport::NetcodeServerPlugin, RenetServerPlugin};
+use bevy_renet::{
+    renet::transport::{generate_random_bytes, NETCODE_KEY_BYTES},
+    transport::NetcodeServerPlugin,
+    RenetServerPlugin,
+};
 use utils::{
-    animation::AnimationPlugin, asset::AssetPlugin as InternalAssetPlugin, deck::DeckPlugin,
-    enums::GameState, input::InputPlugin, physics::PhysicsPlugin, server::ServerPlugin,
+    animation::AnimationPlugin,
+    asset::{resources::AssetConfigPath, AssetPlugin as InternalAssetPlugin},
+    deck::DeckPlugin,
+    enums::GameState,
+    input::InputPlugin,
+    networking::{resources::TickRate, NetworkingPlugin},
+    physics::PhysicsPlugin,
+    player::PlayerPlugin,
+    round::RoundPlugin,
+    score::ScorePlugin,
+    server::{
+        host_server,
+        settings::{encode_key, ServerSettings},
+        token::TokenRequest,
+        ServerPlugin,
+    },
+    settings::SettingsError,
     stats::StatsPlugin,
+    team::TeamPlugin,
 };
 
 fn main() {
+    let mut args = std::env::args().skip(1).peekable();
+
+    match args.peek().map(String::as_str) {
+        Some("token") => return mint_token(args.skip(1)),
+        Some("keygen") => {
+            println!(
+                "{}",
+                encode_key(&generate_random_bytes::<NETCODE_KEY_BYTES>())
+            );
+            return;
+        }
+        _ => {}
+    }
+
+    let settings = match ServerSettings::from_args(args) {
+        Ok(settings) => settings,
+        Err(SettingsError::Help) => {
+            println!("{}", ServerSettings::usage());
+            return;
+        }
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, ServerSettings::usage());
+            process::exit(2);
+        }
+    };
+
     let mut app = App::new();
 
     app.add_plugins((
         MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
-            1.0 / 60.0,
+            1.0 / settings.tick_rate,
         ))),
         AssetPlugin::default(),
+        NetworkingPlugin,
         PhysicsPlugin,
         RenetServerPlugin,
         NetcodeServerPlugin,
@@ -28,9 +75,45 @@ fn main() {
         DeckPlugin,
         InputPlugin,
         StatsPlugin,
+        PlayerPlugin,
+        (ScorePlugin, RoundPlugin, TeamPlugin),
     ));
 
+    if let Err(error) = host_server(&mut app, &settings) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+
+    app.insert_resource(TickRate(settings.tick_rate));
+    app.insert_resource(AssetConfigPath(settings.asset_config.clone()));
+
     app.add_state::<GameState>();
 
     app.run();
 }
+
+fn mint_token(args: impl Iterator<Item = String>) {
+    let token_request = match TokenRequest::from_args(args) {
+        Ok(token_request) => token_request,
+        Err(SettingsError::Help) => {
+            println!("{}", TokenRequest::usage());
+            return;
+        }
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, TokenRequest::usage());
+            process::exit(2);
+        }
+    };
+
+    if let Err(error) = token_request.write() {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+
+    println!(
+        "Connect token for {} ({}) written to {}",
+        token_request.user_data.name,
+        token_request.user_data.id,
+        token_request.out.display()
+    );
+}
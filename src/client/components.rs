@@ -0,0 +1,92 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::{Component, Vec2, Vec3};
+
+/// A server snapshot of a remote entity
+#[derive(Debug, Clone, Copy)]
+pub struct Snapshot {
+    pub tick: u64,
+    pub translation: Vec3,
+    pub aim: Vec2,
+}
+
+/**
+ * Snapshot Buffer
+ *
+ * The most recent server snapshots of a remote entity,
+ * used to render it between snapshots
+ */
+#[derive(Component, Debug)]
+pub struct SnapshotBuffer {
+    pub snapshots: VecDeque<Snapshot>,
+    pub capacity: usize,
+}
+
+impl Default for SnapshotBuffer {
+    fn default() -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            capacity: 32,
+        }
+    }
+}
+
+impl SnapshotBuffer {
+    /// Buffers a snapshot, snapshots older than the latest are out of order and dropped
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if let Some(latest) = self.snapshots.back() {
+            if snapshot.tick <= latest.tick {
+                return;
+            }
+        }
+
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+
+        self.snapshots.push_back(snapshot);
+    }
+
+    /// Drops snapshots no longer needed to render the given tick
+    pub fn discard_before(&mut self, tick: f64) {
+        while self.snapshots.len() > 2 && self.snapshots[1].tick as f64 <= tick {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// The translation and aim of the entity at the given tick
+    ///
+    /// Interpolates between the two snapshots surrounding the tick, and
+    /// extrapolates from the last two snapshots for at most `max_extrapolation` ticks
+    pub fn sample(&self, tick: f64, max_extrapolation: f64) -> Option<(Vec3, Vec2)> {
+        let first = self.snapshots.front()?;
+        let latest = self.snapshots.back()?;
+
+        if tick <= first.tick as f64 {
+            return Some((first.translation, first.aim));
+        }
+
+        for (from, to) in self.snapshots.iter().zip(self.snapshots.iter().skip(1)) {
+            if tick <= to.tick as f64 {
+                let t = ((tick - from.tick as f64) / (to.tick - from.tick) as f64) as f32;
+                return Some((
+                    from.translation.lerp(to.translation, t),
+                    from.aim.lerp(to.aim, t),
+                ));
+            }
+        }
+
+        if self.snapshots.len() < 2 {
+            return Some((latest.translation, latest.aim));
+        }
+
+        let previous = &self.snapshots[self.snapshots.len() - 2];
+        let ahead = (tick - latest.tick as f64).min(max_extrapolation);
+        let t = (ahead / (latest.tick - previous.tick) as f64) as f32;
+
+        Some((
+            latest.translation + (latest.translation - previous.translation) * t,
+            latest.aim + (latest.aim - previous.aim) * t,
+        ))
+    }
+}
@@ -1,78 +1,83 @@
-use std::{net::UdpSocket, time::SystemTime};
-
 use bevy::prelude::*;
-use bevy_renet::{
-    client_connected,
-    renet::{
-        transport::{ClientAuthentication, NetcodeClientTransport, NetcodeTransportError},
-        RenetClient,
-    },
-};
+use bevy_renet::client_connected;
 
-use self::resources::*;
+use self::{resources::*, settings::ConnectionSettings};
 use crate::{
     client::sets::Connected,
     enums::GameState,
-    networking::config::{connection_config, PROTOCOL_ID},
+    networking::{resources::NetworkRegistry, sets::SimulationSet},
 };
 
-use self::systems::client_update_system;
+use self::systems::{
+    advance_server_clock, begin_connecting, client_handshake_system, client_sync_system,
+    client_update_system, close_connection, detect_disconnect_system, interpolate_remote_entities,
+    open_connection_system, record_predicted_state, reset_client_world, return_to_connect_system,
+    watch_connection_system,
+};
 
+pub mod components;
+pub mod params;
 pub mod resources;
 pub mod sets;
+pub mod settings;
 mod systems;
 
 pub struct ClientPlugin;
 
 impl Plugin for ClientPlugin {
     fn build(&self, app: &mut App) {
-        self.connect_client_and_network_systems(app);
+        app.configure_sets(Update, Connected.run_if(client_connected()));
 
+        app.insert_resource(ClientLobby::default());
+        app.insert_resource(NetworkRegistry::default());
+        app.insert_resource(PredictionHistory::default());
+        app.insert_resource(ServerClock::default());
+        app.insert_resource(ReceivedSnapshots::default());
+        app.insert_resource(SentInputs::default());
+        app.insert_resource(SnapshotInterpolation::default());
+        app.insert_resource(ConnectionAttempt::default());
+        app.insert_resource(ClientDisconnectReason::default());
+        app.init_resource::<ConnectionSettings>();
+
+        app.add_systems(OnEnter(GameState::Connecting), begin_connecting);
         app.add_systems(
             Update,
-            client_update_system.run_if(in_state(GameState::Gameloop)),
+            (
+                open_connection_system,
+                watch_connection_system,
+                client_handshake_system,
+            )
+                .chain()
+                .run_if(in_state(GameState::Connecting)),
+        );
+        app.add_systems(
+            Update,
+            detect_disconnect_system.run_if(in_state(GameState::Gameloop)),
+        );
+        app.add_systems(OnExit(GameState::Gameloop), reset_client_world);
+        app.add_systems(OnEnter(GameState::Disconnected), close_connection);
+        app.add_systems(
+            Update,
+            return_to_connect_system.run_if(in_state(GameState::Disconnected)),
         );
-    }
-}
-
-impl ClientPlugin {
-    /// Connnect to the server
-    /// and add any required resources, and systems.
-    fn connect_client_and_network_systems(&self, app: &mut App) {
-        // TODO turn this into a system that runs once in connecting game state
-        let client = RenetClient::new(connection_config());
-
-        let server_addr = "127.0.0.1:5000".parse().unwrap();
-        // let server_addr = "138.197.16.199:5000".parse().unwrap();
-        let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
-        let current_time = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap();
-        let client_id = current_time.as_millis() as u64;
-        let authentication = ClientAuthentication::Unsecure {
-            client_id,
-            protocol_id: PROTOCOL_ID,
-            server_addr,
-            user_data: None,
-        };
-
-        let transport = NetcodeClientTransport::new(current_time, authentication, socket).unwrap();
-
-        app.configure_sets(Update, Connected.run_if(client_connected()));
-        app.insert_resource(client);
-        app.insert_resource(transport);
-
-        app.insert_resource(ClientLobby::default());
-        app.insert_resource(CurrentClientId(client_id));
-        app.insert_resource(NetworkEntities::default());
 
-        // If any error is found we just panic
-        fn panic_on_error_system(mut renet_error: EventReader<NetcodeTransportError>) {
-            for e in renet_error.read() {
-                panic!("{:?}", e);
-            }
-        }
+        app.add_systems(
+            Update,
+            (
+                advance_server_clock,
+                client_update_system,
+                client_sync_system.after(advance_server_clock),
+                interpolate_remote_entities.after(client_sync_system),
+            )
+                .run_if(in_state(GameState::Gameloop)),
+        );
 
-        app.add_systems(Update, panic_on_error_system.in_set(Connected));
+        app.add_systems(
+            FixedUpdate,
+            record_predicted_state
+                .in_set(SimulationSet::Sync)
+                .run_if(in_state(GameState::Gameloop))
+                .run_if(client_connected()),
+        );
     }
 }
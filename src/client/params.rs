@@ -0,0 +1,117 @@
+use bevy::ecs::{
+    event::EventWriter,
+    system::{Res, ResMut, SystemParam},
+};
+
+use crate::{
+    deck::{
+        card::equipment::events::{
+            ChangeEquipmentEvent, EquipEquipmentEvent, ReloadEquipmentEvent,
+        },
+        events::HandEvent,
+        keyword::events::{
+            DamageEntityEvent, DespawnProjectileEvent, KeywordOutcomeEvent, SpawnProjectileEvent,
+        },
+    },
+    networking::{networking::ServerMessages, resources::TickRate},
+    player::events::{CreatePlayerEvent, RemovePlayerEvent, RespawnPlayerEvent},
+    round::events::MatchStateEvent,
+    score::events::{KillFeedEvent, ScoreboardEvent},
+    stats::events::StatusEffectsEvent,
+    team::events::PlayerTeamEvent,
+};
+
+use super::resources::ServerClock;
+
+/// Forwards the messages received from the server to the events client systems read
+#[derive(SystemParam)]
+pub struct ServerMessageEvents<'w> {
+    player_create: EventWriter<'w, CreatePlayerEvent>,
+    player_remove: EventWriter<'w, RemovePlayerEvent>,
+    player_respawn: EventWriter<'w, RespawnPlayerEvent>,
+    spawn_projectile: EventWriter<'w, SpawnProjectileEvent>,
+    despawn_projectile: EventWriter<'w, DespawnProjectileEvent>,
+    damage_entity: EventWriter<'w, DamageEntityEvent>,
+    reload_equipment: EventWriter<'w, ReloadEquipmentEvent>,
+    kill_feed: EventWriter<'w, KillFeedEvent>,
+    scoreboard: EventWriter<'w, ScoreboardEvent>,
+    match_state: EventWriter<'w, MatchStateEvent>,
+    player_team: EventWriter<'w, PlayerTeamEvent>,
+    change_equipment: EventWriter<'w, ChangeEquipmentEvent>,
+    hand: EventWriter<'w, HandEvent>,
+    equip_equipment: EventWriter<'w, EquipEquipmentEvent>,
+    keyword_outcome: EventWriter<'w, KeywordOutcomeEvent>,
+    status_effects: EventWriter<'w, StatusEffectsEvent>,
+}
+
+impl ServerMessageEvents<'_> {
+    pub fn send(&mut self, message: ServerMessages) {
+        match message {
+            ServerMessages::PlayerCreate(player_create_event) => {
+                self.player_create.send(player_create_event);
+            }
+            ServerMessages::PlayerRemove(player_remove_event) => {
+                self.player_remove.send(player_remove_event);
+            }
+            ServerMessages::SpawnProjectile(spawn_projectile_event) => {
+                self.spawn_projectile.send(spawn_projectile_event);
+            }
+            ServerMessages::DespawnProjectile(despawn_projectile_event) => {
+                self.despawn_projectile.send(despawn_projectile_event);
+            }
+            ServerMessages::DamageEntity(damage_entity_event) => {
+                self.damage_entity.send(damage_entity_event);
+            }
+            ServerMessages::ReloadEquipment(reload_equipment_event) => {
+                self.reload_equipment.send(reload_equipment_event);
+            }
+            ServerMessages::Warning(warning) => {
+                println!("Warning from the server: {}", warning);
+            }
+            ServerMessages::PlayerRespawn(respawn_player_event) => {
+                self.player_respawn.send(respawn_player_event);
+            }
+            ServerMessages::KillFeed(kill_feed_event) => {
+                self.kill_feed.send(kill_feed_event);
+            }
+            ServerMessages::Scoreboard(scoreboard_event) => {
+                self.scoreboard.send(scoreboard_event);
+            }
+            ServerMessages::MatchState(match_state_event) => {
+                self.match_state.send(match_state_event);
+            }
+            ServerMessages::PlayerTeam(player_team_event) => {
+                self.player_team.send(player_team_event);
+            }
+            ServerMessages::ChangeEquipment(change_equipment_event) => {
+                self.change_equipment.send(change_equipment_event);
+            }
+            ServerMessages::Hand(hand_event) => {
+                self.hand.send(hand_event);
+            }
+            ServerMessages::EquipEquipment(equip_equipment_event) => {
+                self.equip_equipment.send(equip_equipment_event);
+            }
+            ServerMessages::KeywordOutcome(keyword_outcome_event) => {
+                self.keyword_outcome.send(keyword_outcome_event);
+            }
+            ServerMessages::StatusEffects(status_effects_event) => {
+                self.status_effects.send(status_effects_event);
+            }
+        }
+    }
+}
+
+/// The client's estimate of the server tick, kept at the server's tick rate
+#[derive(SystemParam)]
+pub struct ServerTime<'w> {
+    server_clock: ResMut<'w, ServerClock>,
+    tick_rate: Res<'w, TickRate>,
+}
+
+impl ServerTime<'_> {
+    /// Moves the estimate towards a tick received from the server
+    pub fn observe(&mut self, tick: u64) {
+        self.server_clock.observe(tick, **self.tick_rate);
+    }
+}
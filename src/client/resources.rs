@@ -1,8 +1,19 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
 
-use bevy::prelude::{Deref, Entity, Resource};
+use bevy::{
+    prelude::{Deref, Entity, Resource, Vec2, Vec3},
+    time::Timer,
+};
 use serde::{Deserialize, Serialize};
 
+use crate::networking::{
+    components::NetworkId,
+    models::{InputPacket, NetworkedEntities, PackedInput, QuantizedEntity},
+};
+
 /// A struct that holds a client id
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Deref)]
 pub struct ClientId(pub u64);
@@ -11,11 +22,49 @@ pub struct ClientId(pub u64);
 #[derive(Debug, Resource)]
 pub struct CurrentClientId(pub u64);
 
-/// A struct that holds the server and the client's attached entity
+/// Progress of the connection to the server while connecting
+#[derive(Debug, Default, Resource)]
+pub struct ConnectionAttempt {
+    /// The number of attempts made so far
+    pub attempt: u32,
+    /// Time spent on the current attempt
+    pub elapsed: Duration,
+    /// Delay before the next attempt, when the last one failed
+    pub retry: Option<Timer>,
+    pub last_error: Option<String>,
+    /// Whether the handshake was sent on the current attempt
+    pub handshake_sent: bool,
+}
+
+/// Why the client was disconnected, shown on the disconnected screen
+#[derive(Debug, Default, Resource, Deref)]
+pub struct ClientDisconnectReason(pub String);
+
+/// The last inputs sent to the server, repeated in every input packet
+#[derive(Debug, Default, Resource)]
+pub struct SentInputs {
+    pub inputs: VecDeque<PackedInput>,
+}
+
+impl SentInputs {
+    /// Adds the newest input and builds the packet carrying it
+    pub fn packet(&mut self, input: PackedInput) -> InputPacket {
+        if self.inputs.len() == InputPacket::REDUNDANCY {
+            self.inputs.pop_front();
+        }
+        self.inputs.push_back(input);
+
+        InputPacket {
+            inputs: self.inputs.iter().copied().collect(),
+        }
+    }
+}
+
+/// A struct that holds the network id and the client's attached entity
 #[derive(Debug)]
 pub struct PlayerInfo {
     pub client_entity: Entity,
-    pub server_entity: Entity,
+    pub network_id: NetworkId,
 }
 
 /// A struct that holds the client's within the lobby
@@ -24,6 +73,236 @@ pub struct ClientLobby {
     pub players: HashMap<ClientId, PlayerInfo>,
 }
 
-/// A HashMap of Server Synced Network Entities
-#[derive(Default, Resource)]
-pub struct NetworkEntities(pub HashMap<Entity, Entity>);
+/// A locally predicted movement step of the controlled player
+#[derive(Debug, Clone, Copy)]
+pub struct PredictedState {
+    pub sequence: u32,
+    pub velocity: Vec2,
+    pub delta: f32,
+    pub translation: Vec3,
+}
+
+/// A ring buffer of the controlled player's predicted states
+/// that the server has not acknowledged yet
+#[derive(Debug, Resource)]
+pub struct PredictionHistory {
+    pub states: VecDeque<PredictedState>,
+    pub capacity: usize,
+    /// How far the prediction may drift from the server before it is corrected
+    pub tolerance: f32,
+}
+
+impl Default for PredictionHistory {
+    fn default() -> Self {
+        Self {
+            states: VecDeque::new(),
+            capacity: 128,
+            tolerance: 2.0,
+        }
+    }
+}
+
+impl PredictionHistory {
+    pub fn push(&mut self, state: PredictedState) {
+        if self.states.len() == self.capacity {
+            self.states.pop_front();
+        }
+
+        self.states.push_back(state);
+    }
+
+    /// Reconcile the prediction with an authoritative server position.
+    ///
+    /// Drops every state the server has processed, then replays the remaining
+    /// inputs from the server position. Returns the corrected translation when
+    /// the prediction has drifted further than the tolerance.
+    pub fn reconcile(
+        &mut self,
+        last_processed_input: u32,
+        server_translation: Vec3,
+        current_translation: Vec3,
+    ) -> Option<Vec3> {
+        while self
+            .states
+            .front()
+            .is_some_and(|state| state.sequence <= last_processed_input)
+        {
+            self.states.pop_front();
+        }
+
+        let replayed = self
+            .states
+            .iter()
+            .fold(server_translation, |translation, state| {
+                translation + (state.velocity * state.delta).extend(0.0)
+            });
+
+        if replayed.distance(current_translation) <= self.tolerance {
+            return None;
+        }
+
+        // rebase the remaining predictions on the corrected path
+        let mut translation = server_translation;
+        for state in self.states.iter_mut() {
+            translation += (state.velocity * state.delta).extend(0.0);
+            state.translation = translation;
+        }
+
+        Some(replayed)
+    }
+}
+
+/// Settings for rendering remote entities from buffered snapshots
+#[derive(Debug, Resource)]
+pub struct SnapshotInterpolation {
+    /// How far in the past, in seconds, remote entities are rendered
+    pub delay: f32,
+    /// How long, in seconds, motion is extrapolated when snapshots are missing
+    pub max_extrapolation: f32,
+}
+
+impl Default for SnapshotInterpolation {
+    fn default() -> Self {
+        Self {
+            delay: 0.1,
+            max_extrapolation: 0.25,
+        }
+    }
+}
+
+/// The client's estimate of the current server tick
+#[derive(Debug, Default, Resource)]
+pub struct ServerClock {
+    pub tick: f64,
+    pub synced: bool,
+    /// The tick remote entities are currently rendered at, behind the estimate by the interpolation delay
+    pub render_tick: f64,
+}
+
+impl ServerClock {
+    /// Moves the estimate towards a tick received from the server,
+    /// jumping straight to it when the estimate is too far off
+    pub fn observe(&mut self, tick: u64, tick_rate: f64) {
+        let tick = tick as f64;
+        if !self.synced || (tick - self.tick).abs() > tick_rate / 2.0 {
+            self.tick = tick;
+            self.synced = true;
+        } else if tick > self.tick {
+            self.tick += (tick - self.tick) * 0.1;
+        }
+    }
+}
+
+/// The decoded snapshots recently received from the server,
+/// kept as baselines for the deltas that follow
+#[derive(Debug, Default, Resource)]
+pub struct ReceivedSnapshots {
+    pub snapshots: VecDeque<(u64, HashMap<NetworkId, QuantizedEntity>)>,
+}
+
+impl ReceivedSnapshots {
+    const CAPACITY: usize = 64;
+
+    /// Rebuilds the full snapshot from its deltas and stores it.
+    /// Returns `None` when the baseline it was delta-encoded against is no longer held
+    pub fn decode(
+        &mut self,
+        networked_entities: &NetworkedEntities,
+    ) -> Option<HashMap<NetworkId, QuantizedEntity>> {
+        let mut entities = match networked_entities.baseline {
+            Some(baseline) => self
+                .snapshots
+                .iter()
+                .find(|(tick, _)| *tick == baseline)
+                .map(|(_, entities)| entities.clone())?,
+            None => HashMap::new(),
+        };
+
+        for network_id in networked_entities.removed.iter() {
+            entities.remove(network_id);
+        }
+
+        for delta in networked_entities.entities.iter() {
+            if let Some(entity) = delta.apply(entities.get(&delta.network_id)) {
+                entities.insert(delta.network_id, entity);
+            }
+        }
+
+        if self.snapshots.len() == Self::CAPACITY {
+            self.snapshots.pop_front();
+        }
+        self.snapshots
+            .push_back((networked_entities.tick, entities.clone()));
+
+        Some(entities)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{enums::EntityState, networking::models::EntityDelta};
+
+    fn entity(x: f32) -> QuantizedEntity {
+        QuantizedEntity::new(Vec3::new(x, 0.0, 0.0), Vec2::ZERO, EntityState::Idle)
+    }
+
+    fn full_snapshot(tick: u64, ids: &[u64]) -> NetworkedEntities {
+        NetworkedEntities {
+            tick,
+            entities: ids
+                .iter()
+                .filter_map(|&id| EntityDelta::between(NetworkId(id), None, &entity(id as f32)))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn removed_entities_are_dropped_from_the_baseline() {
+        let mut received_snapshots = ReceivedSnapshots::default();
+        received_snapshots.decode(&full_snapshot(1, &[1, 2, 3]));
+
+        let moved = entity(50.0);
+        let delta = NetworkedEntities {
+            tick: 2,
+            baseline: Some(1),
+            entities: EntityDelta::between(NetworkId(1), Some(&entity(1.0)), &moved)
+                .into_iter()
+                .collect(),
+            removed: vec![NetworkId(2)],
+            ..Default::default()
+        };
+
+        let entities = received_snapshots.decode(&delta).unwrap();
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[&NetworkId(1)], moved);
+        assert_eq!(entities[&NetworkId(3)], entity(3.0));
+        assert!(!entities.contains_key(&NetworkId(2)));
+
+        // the decoded snapshot becomes a baseline of its own
+        let next = NetworkedEntities {
+            tick: 3,
+            baseline: Some(2),
+            removed: vec![NetworkId(3)],
+            ..Default::default()
+        };
+        let entities = received_snapshots.decode(&next).unwrap();
+        assert_eq!(entities.keys().collect::<Vec<_>>(), vec![&NetworkId(1)]);
+    }
+
+    #[test]
+    fn deltas_against_unknown_baselines_are_skipped() {
+        let mut received_snapshots = ReceivedSnapshots::default();
+        received_snapshots.decode(&full_snapshot(1, &[1]));
+
+        let delta = NetworkedEntities {
+            tick: 3,
+            baseline: Some(2),
+            removed: vec![NetworkId(1)],
+            ..Default::default()
+        };
+
+        assert!(received_snapshots.decode(&delta).is_none());
+    }
+}
@@ -0,0 +1,117 @@
+use std::{net::SocketAddr, path::PathBuf, time::Duration};
+
+use bevy::prelude::Resource;
+
+use crate::{
+    networking::{config::PROTOCOL_ID, models::PlayerUserData},
+    settings::{parse, SettingsError},
+};
+
+const USAGE: &str = "\
+Usage: client [OPTIONS]
+
+Options:
+  --server <ADDR>         Address of the server to connect to
+  --protocol-id <ID>      Override the netcode protocol id
+  --timeout <SECONDS>     How long a single connection attempt may take
+  --attempts <N>          Connection attempts before giving up
+  --name <NAME>           Display name, when connecting without a token
+  --token <PATH>          Connect with a token minted by the server, for secure servers
+  -h, --help              Print this message";
+
+/// Where and how the client connects to the server
+#[derive(Debug, Clone, Resource)]
+pub struct ConnectionSettings {
+    pub server_addr: SocketAddr,
+    pub protocol_id: u64,
+    /// How long a single attempt may take before it is retried
+    pub attempt_timeout: Duration,
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled after every failed attempt
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub name: String,
+    /// Connect token file, the address, protocol id, and name are taken from it when set
+    pub connect_token: Option<PathBuf>,
+}
+
+impl Default for ConnectionSettings {
+    fn default() -> Self {
+        Self {
+            server_addr: SocketAddr::from(([127, 0, 0, 1], 5000)),
+            protocol_id: PROTOCOL_ID,
+            attempt_timeout: Duration::from_secs(5),
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+            name: "Player".to_string(),
+            connect_token: None,
+        }
+    }
+}
+
+impl ConnectionSettings {
+    /// Builds the settings from the command-line arguments, without the program name
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, SettingsError> {
+        let mut settings = Self::default();
+
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            if flag == "-h" || flag == "--help" {
+                return Err(SettingsError::Help);
+            }
+
+            let value = args
+                .next()
+                .ok_or_else(|| SettingsError::MissingValue(flag.clone()))?;
+
+            match flag.as_str() {
+                "--server" => settings.server_addr = parse(&flag, &value)?,
+                "--protocol-id" => settings.protocol_id = parse(&flag, &value)?,
+                "--timeout" => {
+                    let seconds: f64 = parse(&flag, &value)?;
+                    settings.attempt_timeout = Duration::try_from_secs_f64(seconds)
+                        .map_err(|_| SettingsError::InvalidValue(flag.clone(), value.clone()))?;
+                }
+                "--attempts" => settings.max_attempts = parse(&flag, &value)?,
+                "--name" => settings.name = value,
+                "--token" => settings.connect_token = Some(PathBuf::from(value)),
+                _ => return Err(SettingsError::UnknownFlag(flag)),
+            }
+        }
+
+        if settings.max_attempts == 0 {
+            return Err(SettingsError::Invalid(
+                "at least one connection attempt is required".to_string(),
+            ));
+        }
+
+        if settings.name.is_empty()
+            || settings.name.chars().count() > PlayerUserData::MAX_NAME_LENGTH
+        {
+            return Err(SettingsError::Invalid(format!(
+                "name must be between 1 and {} characters",
+                PlayerUserData::MAX_NAME_LENGTH
+            )));
+        }
+
+        if settings.attempt_timeout.is_zero() {
+            return Err(SettingsError::Invalid(
+                "connection timeout must be above 0".to_string(),
+            ));
+        }
+
+        Ok(settings)
+    }
+
+    /// The delay before retrying after the given failed attempt
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff)
+    }
+
+    pub fn usage() -> &'static str {
+        USAGE
+    }
+}
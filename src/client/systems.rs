@@ -1,95 +1,484 @@
+use std::{
+    error::Error,
+    fs::File,
+    net::UdpSocket,
+    time::{Duration, SystemTime},
+};
+
 use bevy::{
-    ecs::system::Query,
-    prelude::{Commands, EventWriter, ResMut, Transform},
+    ecs::{
+        query::{With, Without},
+        system::Query,
+    },
+    hierarchy::DespawnRecursiveExt,
+    input::{keyboard::KeyCode, mouse::MouseButton, Input},
+    prelude::{Commands, Entity, EventReader, EventWriter, NextState, Res, ResMut, Transform},
+    time::{Time, Timer, TimerMode},
+};
+use bevy_renet::renet::{
+    transport::{
+        ClientAuthentication, ConnectToken, NetcodeClientTransport, NetcodeTransportError,
+    },
+    RenetClient,
 };
-use bevy_renet::renet::RenetClient;
 
 use crate::{
     animation::events::PlayAnimationEvent,
-    deck::keyword::events::{DamageEntityEvent, SpawnProjectileEvent},
-    enums::EntityState::Dead,
-    input::components::{Aim, Controllable},
-    networking::{channels::ServerChannel, models::NetworkedEntities, networking::ServerMessages},
-    player::{
-        components::Death,
-        events::{CreatePlayerEvent, RemovePlayerEvent},
+    asset::resources::{AssetConfigHash, AssetRegistry},
+    deck::resources::ClientHand,
+    enums::{EntityState::Dead, GameState},
+    input::{
+        components::{Aim, Controllable, PlayerCamera},
+        resources::PlayerInput,
+    },
+    networking::{
+        channels::{ClientChannel, ServerChannel},
+        codec,
+        components::NetworkId,
+        config::connection_config,
+        models::{Handshake, HandshakeResponse, NetworkedEntities, PlayerUserData, SnapshotAck},
+        networking::ServerMessageEnvelope,
+        resources::{NetworkRegistry, TickRate},
     },
+    physics::components::Velocity,
+    player::components::Death,
+    round::resources::MatchState,
+    score::resources::{ClientScoreboard, KillFeed},
 };
 
-use super::resources::NetworkEntities;
+use super::{
+    components::{Snapshot, SnapshotBuffer},
+    params::{ServerMessageEvents, ServerTime},
+    resources::{
+        ClientDisconnectReason, ClientLobby, ConnectionAttempt, CurrentClientId, PredictedState,
+        PredictionHistory, ReceivedSnapshots, SentInputs, ServerClock, SnapshotInterpolation,
+    },
+    settings::ConnectionSettings,
+};
 
 pub fn client_update_system(
-    mut writer_player_create: EventWriter<CreatePlayerEvent>,
-    mut writer_player_remove: EventWriter<RemovePlayerEvent>,
-    mut writer_spawn_projectile: EventWriter<SpawnProjectileEvent>,
-    mut writer_damage_entity: EventWriter<DamageEntityEvent>,
-    mut writer_play_animation: EventWriter<PlayAnimationEvent>,
+    mut server_message_events: ServerMessageEvents,
     mut client: ResMut<RenetClient>,
-    network_mapping: ResMut<NetworkEntities>,
-    mut commands: Commands,
-    query: Query<Option<&Controllable>>,
 ) {
     while let Some(message) = client.receive_message(ServerChannel::ServerMessages) {
-        let server_message = bincode::deserialize::<ServerMessages>(&message);
-        if server_message.is_err() {
-            println!(
-                "Failed to deserialize server message {:?}",
-                server_message.unwrap_err()
-            );
-            continue;
-        }
-        let server_message = server_message.unwrap();
-        match server_message {
-            ServerMessages::PlayerCreate(player_create_event) => {
-                writer_player_create.send(player_create_event);
-            }
-            ServerMessages::PlayerRemove(player_remove_event) => {
-                writer_player_remove.send(player_remove_event);
-            }
-            ServerMessages::SpawnProjectile(spawn_projectile_event) => {
-                writer_spawn_projectile.send(spawn_projectile_event);
-            }
-            ServerMessages::DamageEntity(damage_entity_event) => {
-                writer_damage_entity.send(damage_entity_event);
+        let limit = ServerChannel::ServerMessages.max_message_bytes();
+        let server_message = match codec::decode::<ServerMessageEnvelope>(&message, limit)
+            .and_then(|envelope| envelope.open(limit))
+        {
+            Ok(Some(server_message)) => server_message,
+            // sent by a newer server, this build has no use for it
+            Ok(None) => continue,
+            Err(error) => {
+                println!("Failed to decode server message {}", error);
+                continue;
             }
         };
+
+        server_message_events.send(server_message);
     }
+}
+
+pub fn client_sync_system(
+    mut writer_play_animation: EventWriter<PlayAnimationEvent>,
+    mut client: ResMut<RenetClient>,
+    network_registry: Res<NetworkRegistry>,
+    mut received_snapshots: ResMut<ReceivedSnapshots>,
+    mut prediction_history: ResMut<PredictionHistory>,
+    mut server_time: ServerTime,
+    mut commands: Commands,
+    mut query: Query<(
+        Option<&Controllable>,
+        &Transform,
+        Option<&mut SnapshotBuffer>,
+    )>,
+) {
+    let mut latest_tick = None;
 
     while let Some(message) = client.receive_message(ServerChannel::NetworkedEntities) {
-        let networked_entities: NetworkedEntities = bincode::deserialize(&message).unwrap();
+        let networked_entities: NetworkedEntities = match codec::decode(
+            &message,
+            ServerChannel::NetworkedEntities.max_message_bytes(),
+        ) {
+            Ok(networked_entities) => networked_entities,
+            Err(error) => {
+                println!("Failed to decode networked entities {}", error);
+                continue;
+            }
+        };
+
+        // without the baseline the deltas can't be applied, the server
+        // falls back to an older baseline until this client acknowledges a newer one
+        let Some(entities) = received_snapshots.decode(&networked_entities) else {
+            continue;
+        };
+
+        server_time.observe(networked_entities.tick);
+        latest_tick = latest_tick.max(Some(networked_entities.tick));
 
         // TODO: Possibly worth breaking out into seperate event reader stream
-        for i in 0..networked_entities.entities.len() {
+        for (network_id, quantized_entity) in entities.iter() {
             // If we don't have the synced entity, currently just skip it
             // TODO: Consider a factory implementation similar to Litihum here
-            if let Some(entity) = network_mapping.0.get(&networked_entities.entities[i]) {
-                let translation = networked_entities.translations[i].into();
-                let transform = Transform {
-                    translation,
-                    ..Default::default()
-                };
-                let state = networked_entities.states[i];
-                let aim = Aim(networked_entities.aim_ats[i].into());
-                if let Some(mut entity_command) = commands.get_entity(*entity) {
-                    if let Ok(controllable) = query.get(*entity) {
-                        if let Some(_) = controllable {
-                            // if local player is controlling
-                            // we should only lerp the position, if it's very inaccurate
-                            // and we don't need to update the aim.
-                            entity_command.insert((transform, state));
+            if let Some(entity) = network_registry.entity(network_id) {
+                let translation = quantized_entity.translation();
+                let state = quantized_entity.state;
+                if let Some(mut entity_command) = commands.get_entity(entity) {
+                    if let Ok((controllable, current_transform, snapshot_buffer)) =
+                        query.get_mut(entity)
+                    {
+                        if controllable.is_some() {
+                            // if local player is controlling, the position is predicted
+                            // and is only corrected when it has drifted from the server.
+                            // we don't need to update the aim.
+                            if let Some(translation) = prediction_history.reconcile(
+                                networked_entities.last_processed_input,
+                                translation,
+                                current_transform.translation,
+                            ) {
+                                entity_command.insert(Transform {
+                                    translation,
+                                    ..*current_transform
+                                });
+                            }
+                            entity_command.insert(state);
                         } else {
-                            // TODO: Lerp transform
-                            entity_command.insert((transform, state, aim));
+                            // remote entities are rendered from their buffered snapshots
+                            if let Some(mut snapshot_buffer) = snapshot_buffer {
+                                snapshot_buffer.push(Snapshot {
+                                    tick: networked_entities.tick,
+                                    translation,
+                                    aim: quantized_entity.aim(),
+                                });
+                            }
+                            entity_command.insert(state);
                         }
 
                         if state == Dead {
                             entity_command.insert(Death::default());
+                        } else {
+                            // respawned, in case the snapshot arrived before the respawn message
+                            entity_command.remove::<Death>();
                         }
                     }
                 }
 
-                writer_play_animation.send(PlayAnimationEvent::new(*entity, &state.to_string()));
+                writer_play_animation.send(PlayAnimationEvent::new(entity, &state.to_string()));
             }
         }
     }
+
+    if let Some(tick) = latest_tick {
+        let message = codec::encode(&SnapshotAck { tick });
+        client.send_message(ClientChannel::SnapshotAck, message);
+    }
+}
+
+pub fn record_predicted_state(
+    dt: Res<Time>,
+    mut prediction_history: ResMut<PredictionHistory>,
+    query: Query<(&PlayerInput, &Velocity, &Transform), With<Controllable>>,
+) {
+    if let Ok((player_input, velocity, transform)) = query.get_single() {
+        prediction_history.push(PredictedState {
+            sequence: player_input.sequence,
+            velocity: velocity.vector,
+            delta: dt.delta_seconds(),
+            translation: transform.translation,
+        });
+    }
+}
+
+pub fn advance_server_clock(
+    dt: Res<Time>,
+    tick_rate: Res<TickRate>,
+    mut server_clock: ResMut<ServerClock>,
+) {
+    if server_clock.synced {
+        server_clock.tick += dt.delta_seconds_f64() * **tick_rate;
+    }
+}
+
+pub fn interpolate_remote_entities(
+    mut server_clock: ResMut<ServerClock>,
+    snapshot_interpolation: Res<SnapshotInterpolation>,
+    tick_rate: Res<TickRate>,
+    mut query: Query<(&mut SnapshotBuffer, &mut Transform, &mut Aim), Without<Controllable>>,
+) {
+    let render_tick = server_clock.tick - snapshot_interpolation.delay as f64 * **tick_rate;
+    server_clock.render_tick = render_tick;
+    let max_extrapolation = snapshot_interpolation.max_extrapolation as f64 * **tick_rate;
+
+    for (mut snapshot_buffer, mut transform, mut aim) in &mut query {
+        if let Some((translation, aim_at)) = snapshot_buffer.sample(render_tick, max_extrapolation)
+        {
+            transform.translation = translation;
+            aim.0 = aim_at;
+        }
+
+        snapshot_buffer.discard_before(render_tick);
+    }
+}
+
+pub fn begin_connecting(mut connection_attempt: ResMut<ConnectionAttempt>) {
+    // the first attempt is made straight away
+    *connection_attempt = ConnectionAttempt {
+        retry: Some(Timer::default()),
+        ..Default::default()
+    };
+}
+
+pub fn open_connection_system(
+    mut commands: Commands,
+    dt: Res<Time>,
+    connection_settings: Res<ConnectionSettings>,
+    mut connection_attempt: ResMut<ConnectionAttempt>,
+    mut client_disconnect_reason: ResMut<ClientDisconnectReason>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(retry) = connection_attempt.retry.as_mut() else {
+        connection_attempt.elapsed += dt.delta();
+        return;
+    };
+
+    if !retry.tick(dt.delta()).finished() {
+        return;
+    }
+
+    connection_attempt.retry = None;
+    connection_attempt.attempt += 1;
+    connection_attempt.elapsed = Duration::ZERO;
+    connection_attempt.handshake_sent = false;
+
+    match open_connection(&connection_settings) {
+        Ok((client, transport, client_id)) => {
+            commands.insert_resource(client);
+            commands.insert_resource(transport);
+            commands.insert_resource(CurrentClientId(client_id));
+        }
+        // the local socket could not be opened, retrying won't help
+        Err(error) => {
+            client_disconnect_reason.0 = format!(
+                "Could not connect to {}: {}",
+                connection_settings.server_addr, error
+            );
+            next_state.set(GameState::Disconnected);
+        }
+    }
+}
+
+fn open_connection(
+    connection_settings: &ConnectionSettings,
+) -> Result<(RenetClient, NetcodeClientTransport, u64), Box<dyn Error>> {
+    let client = RenetClient::new(connection_config());
+
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?;
+
+    let (client_id, authentication) = match &connection_settings.connect_token {
+        // the server assigned the id when it minted the token
+        Some(path) => {
+            let connect_token = ConnectToken::read(&mut File::open(path)?)?;
+            (
+                connect_token.client_id,
+                ClientAuthentication::Secure { connect_token },
+            )
+        }
+        None => {
+            let client_id = current_time.as_millis() as u64;
+            let user_data = PlayerUserData {
+                id: client_id,
+                name: connection_settings.name.clone(),
+            };
+            (
+                client_id,
+                ClientAuthentication::Unsecure {
+                    client_id,
+                    protocol_id: connection_settings.protocol_id,
+                    server_addr: connection_settings.server_addr,
+                    user_data: user_data.to_bytes(),
+                },
+            )
+        }
+    };
+
+    let transport = NetcodeClientTransport::new(current_time, authentication, socket)?;
+
+    Ok((client, transport, client_id))
+}
+
+pub fn watch_connection_system(
+    mut commands: Commands,
+    mut reader_transport_error: EventReader<NetcodeTransportError>,
+    connection_settings: Res<ConnectionSettings>,
+    mut connection_attempt: ResMut<ConnectionAttempt>,
+    mut client_disconnect_reason: ResMut<ClientDisconnectReason>,
+    mut next_state: ResMut<NextState<GameState>>,
+    client: Option<Res<RenetClient>>,
+) {
+    let transport_error = reader_transport_error
+        .read()
+        .last()
+        .map(|error| error.to_string());
+
+    let Some(client) = client else {
+        return;
+    };
+
+    // the attempt only succeeds once the handshake is accepted,
+    // so the timeout covers the handshake as well
+    let error = transport_error
+        .or_else(|| client.disconnect_reason().map(|reason| reason.to_string()))
+        .or_else(|| {
+            (connection_attempt.elapsed >= connection_settings.attempt_timeout).then(|| {
+                if client.is_connected() {
+                    "handshake timed out".to_string()
+                } else {
+                    "connection timed out".to_string()
+                }
+            })
+        });
+
+    let Some(error) = error else {
+        return;
+    };
+
+    // a failed attempt is torn down and retried after a backoff
+    commands.remove_resource::<RenetClient>();
+    commands.remove_resource::<NetcodeClientTransport>();
+
+    if connection_attempt.attempt >= connection_settings.max_attempts {
+        client_disconnect_reason.0 = format!(
+            "Could not connect to {} after {} attempts: {}",
+            connection_settings.server_addr, connection_attempt.attempt, error
+        );
+        next_state.set(GameState::Disconnected);
+        return;
+    }
+
+    connection_attempt.retry = Some(Timer::new(
+        connection_settings.backoff(connection_attempt.attempt),
+        TimerMode::Once,
+    ));
+    connection_attempt.last_error = Some(error);
+}
+
+/// Sends the handshake once connected and waits for the server to accept it
+pub fn client_handshake_system(
+    client: Option<ResMut<RenetClient>>,
+    asset_config_hash: Res<AssetConfigHash>,
+    mut asset_registry: ResMut<AssetRegistry>,
+    mut connection_attempt: ResMut<ConnectionAttempt>,
+    mut client_disconnect_reason: ResMut<ClientDisconnectReason>,
+    mut tick_rate: ResMut<TickRate>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(mut client) = client else {
+        return;
+    };
+
+    if !client.is_connected() {
+        return;
+    }
+
+    if !connection_attempt.handshake_sent {
+        client.send_message(
+            ClientChannel::Handshake,
+            codec::encode(&Handshake::current(**asset_config_hash)),
+        );
+        connection_attempt.handshake_sent = true;
+    }
+
+    while let Some(message) = client.receive_message(ServerChannel::Handshake) {
+        match codec::decode::<HandshakeResponse>(
+            &message,
+            ServerChannel::Handshake.max_message_bytes(),
+        ) {
+            // the server's ids and tick rate are used for everything it sends from now on
+            Ok(HandshakeResponse::Accepted {
+                registries,
+                tick_rate: server_tick_rate,
+            }) => {
+                if !TickRate(server_tick_rate).is_valid() {
+                    client_disconnect_reason.0 =
+                        format!("Server tick rate {} is not supported.", server_tick_rate);
+                    next_state.set(GameState::Disconnected);
+                    continue;
+                }
+
+                match asset_registry.adopt(registries) {
+                    Ok(()) => {
+                        *tick_rate = TickRate(server_tick_rate);
+                        next_state.set(GameState::Gameloop);
+                    }
+                    Err(error) => {
+                        client_disconnect_reason.0 =
+                            format!("Assets differ from the server's: {}.", error);
+                        next_state.set(GameState::Disconnected);
+                    }
+                }
+            }
+            // a mismatched build won't match on a retry either
+            Ok(HandshakeResponse::Rejected { reason }) => {
+                client_disconnect_reason.0 = format!("Rejected by the server: {}", reason);
+                next_state.set(GameState::Disconnected);
+            }
+            Err(error) => println!("Failed to decode handshake response {}", error),
+        }
+    }
+}
+
+pub fn detect_disconnect_system(
+    mut reader_transport_error: EventReader<NetcodeTransportError>,
+    mut client_disconnect_reason: ResMut<ClientDisconnectReason>,
+    mut next_state: ResMut<NextState<GameState>>,
+    client: Res<RenetClient>,
+) {
+    let error = reader_transport_error
+        .read()
+        .last()
+        .map(|error| error.to_string())
+        .or_else(|| client.disconnect_reason().map(|reason| reason.to_string()));
+
+    if let Some(error) = error {
+        client_disconnect_reason.0 = error;
+        next_state.set(GameState::Disconnected);
+    }
+}
+
+/// Clears everything the server replicated, so a new connection starts from scratch
+pub fn reset_client_world(
+    mut commands: Commands,
+    network_query: Query<Entity, With<NetworkId>>,
+    camera_query: Query<Entity, With<PlayerCamera>>,
+) {
+    for entity in network_query.iter().chain(camera_query.iter()) {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.insert_resource(ClientLobby::default());
+    commands.insert_resource(NetworkRegistry::default());
+    commands.insert_resource(PredictionHistory::default());
+    commands.insert_resource(ServerClock::default());
+    commands.insert_resource(ReceivedSnapshots::default());
+    commands.insert_resource(SentInputs::default());
+    commands.insert_resource(PlayerInput::default());
+    commands.insert_resource(ClientScoreboard::default());
+    commands.insert_resource(KillFeed::default());
+    commands.insert_resource(MatchState::default());
+    commands.insert_resource(ClientHand::default());
+}
+
+pub fn close_connection(mut commands: Commands) {
+    commands.remove_resource::<RenetClient>();
+    commands.remove_resource::<NetcodeClientTransport>();
+}
+
+pub fn return_to_connect_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Return) || mouse_input.just_pressed(MouseButton::Left) {
+        next_state.set(GameState::Connecting);
+    }
 }
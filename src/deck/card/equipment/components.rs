@@ -1,5 +1,7 @@
+use std::time::Duration;
+
 use bevy::{
-    prelude::{Bundle, Component, Handle, Transform, Vec2, Vec3},
+    prelude::{Bundle, Component, Entity, Handle, Transform, Vec2, Vec3},
     sprite::{SpriteSheetBundle, TextureAtlas},
     time::{Timer, TimerMode},
 };
@@ -7,10 +9,11 @@ use bevy::{
 use crate::{
     animation::components::{Animated2DObjectBundle, Animator},
     asset::{
-        enums::{Equipment as EquipmentType, Sprites},
+        registry::{EquipmentName, SpriteName},
         resources::EquipmentStatsConfig,
     },
-    math::{angle_between, vec2_from_vec3},
+    deck::keyword::components::Keyword,
+    math::{angle_between, seeded_unit, vec2_from_vec3},
     physics::components::Velocity,
     stats::components::Speed,
 };
@@ -59,6 +62,59 @@ impl ServerEquipmentBundle {
     }
 }
 
+/**
+ * Inventory
+ *
+ * The equipment a player carries, one child entity per numbered slot,
+ * and the slot in hand. Only the equipment in hand is fired and reloaded
+ */
+#[derive(Debug, Component)]
+pub struct Inventory {
+    pub slots: Vec<Entity>,
+    pub active: usize,
+    /// Counts down a switch to the active slot, nothing can be fired until it finishes
+    pub swap_timer: Timer,
+}
+
+impl Inventory {
+    pub fn new(slots: Vec<Entity>) -> Self {
+        let mut swap_timer = Timer::default();
+        swap_timer.tick(Duration::ZERO);
+
+        Self {
+            slots,
+            active: 0,
+            swap_timer,
+        }
+    }
+
+    /// The equipment in hand
+    pub fn active_equipment(&self) -> Option<Entity> {
+        self.slots.get(self.active).copied()
+    }
+
+    pub fn is_swapping(&self) -> bool {
+        !self.swap_timer.finished()
+    }
+
+    /// Whether the slot exists and isn't already in hand
+    pub fn can_switch(&self, slot: usize) -> bool {
+        slot < self.slots.len() && slot != self.active
+    }
+
+    /// Puts the slot in hand, starting the swap delay
+    pub fn switch(&mut self, slot: usize, swap_delay: f32) {
+        self.active = slot;
+        self.swap_timer = Timer::from_seconds(swap_delay, TimerMode::Once);
+    }
+
+    /// The slot the given number of slots after the active one, wrapping around
+    pub fn cycle(&self, offset: i32) -> usize {
+        let len = self.slots.len().max(1) as i32;
+        (self.active as i32 + offset).rem_euclid(len) as usize
+    }
+}
+
 #[derive(Clone, Debug, Component)]
 pub struct Equipped {
     pub equipment: Equipment,
@@ -66,71 +122,123 @@ pub struct Equipped {
 
 #[derive(Clone, Debug, Component)]
 pub struct Equipment {
-    pub equipment_type: EquipmentType,
+    pub equipment_type: EquipmentName,
+    pub sprite: SpriteName,
     pub magazine: u32,
     pub max_magazine: u32,
     pub fire_rate: f32,
     pub reload_time: f32,
     pub damage: u32,
     pub spray: f32,
-    pub projectile_type: Sprites,
+    pub projectile_type: SpriteName,
     pub projectile_speed: f32,
     pub projectile_size: f32,
     pub projectile_per_shot: u32,
     pub projectile_layer: u32,
     pub projectile_mask: u32,
     pub range: u32,
+    pub keywords: Vec<Keyword>,
     pub fire_rate_timer: Timer,
     pub reload_timer: Timer,
+    pub reloading: bool,
+    pub spray_seed: u64,
 }
 
 impl From<&EquipmentStatsConfig> for Equipment {
     fn from(value: &EquipmentStatsConfig) -> Self {
         Self {
-            equipment_type: value.name,
+            equipment_type: value.name.clone(),
+            sprite: value.sprite.clone(),
             magazine: value.magazine,
             max_magazine: value.max_magazine,
             fire_rate: value.fire_rate,
             reload_time: value.reload_time,
             damage: value.damage,
             spray: value.spray,
-            projectile_type: value.projectile_type,
+            projectile_type: value.projectile_type.clone(),
             range: value.range,
             projectile_speed: value.projectile_speed,
             projectile_size: value.projectile_size,
             projectile_per_shot: value.projectile_per_shot,
             projectile_layer: value.layers.iter().fold(0, |acc, x| acc | *x as u32),
             projectile_mask: value.masks.iter().fold(0, |acc, x| acc | *x as u32),
+            keywords: value.keywords.clone(),
             fire_rate_timer: Timer::from_seconds(value.fire_rate, TimerMode::Once),
             reload_timer: Timer::from_seconds(value.reload_time, TimerMode::Once),
+            reloading: false,
+            spray_seed: 0,
         }
     }
 }
 
 impl Equipment {
-    pub fn use_equipment(&mut self, from: &Vec3, at: &Vec2) -> Velocity {
+    /// The equipment with the seed its first shot spreads by
+    pub fn seeded(self, spray_seed: u64) -> Self {
+        Self { spray_seed, ..self }
+    }
+
+    /// Fires the equipment, returning the velocity of every projectile in the shot
+    pub fn use_equipment(&mut self, from: &Vec3, at: &Vec2) -> Vec<Velocity> {
         self.fire_rate_timer.reset();
         self.magazine -= 1;
 
         let from = vec2_from_vec3(from);
         let angle = angle_between(&from, at);
 
-        Velocity {
-            base_speed: Speed(self.projectile_speed),
-            current_speed: Speed(self.projectile_speed),
-            rotation: angle,
-            vector: Vec2::new(
-                angle.cos() * self.projectile_speed,
-                angle.sin() * self.projectile_speed,
-            ),
-        }
+        let velocities = self
+            .spread(angle, self.spray_seed)
+            .into_iter()
+            .map(|angle| Velocity {
+                base_speed: Speed(self.projectile_speed),
+                current_speed: Speed(self.projectile_speed),
+                rotation: angle,
+                vector: Vec2::new(
+                    angle.cos() * self.projectile_speed,
+                    angle.sin() * self.projectile_speed,
+                ),
+            })
+            .collect();
+
+        self.spray_seed = self.spray_seed.wrapping_add(1);
+
+        velocities
+    }
+
+    /// The angle of each projectile in a shot
+    ///
+    /// Every projectile is offset from `angle` by up to half the spray
+    /// in either direction, derived from the seed and the projectile index
+    pub fn spread(&self, angle: f32, seed: u64) -> Vec<f32> {
+        (0..self.projectile_per_shot.max(1))
+            .map(|index| {
+                let projectile_seed = seed
+                    .wrapping_mul(self.projectile_per_shot as u64 + 1)
+                    .wrapping_add(index as u64);
+                angle + seeded_unit(projectile_seed) * self.spray / 2.0
+            })
+            .collect()
     }
 
     pub fn empty(&self) -> bool {
         self.magazine == 0
     }
 
+    pub fn can_reload(&self) -> bool {
+        !self.reloading && self.magazine < self.max_magazine
+    }
+
+    pub fn start_reload(&mut self) {
+        self.reload_timer.reset();
+        self.reloading = true;
+    }
+
     pub fn reload(&mut self) {
         self.magazine = self.max_magazine;
+        self.reloading = false;
+    }
+
+    /// Stops a reload without refilling the magazine, when the equipment is put away
+    pub fn cancel_reload(&mut self) {
+        self.reloading = false;
     }
 }
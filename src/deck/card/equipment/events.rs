@@ -1,7 +1,61 @@
 use bevy::prelude::{Entity, Event, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::networking::components::NetworkId;
 
 #[derive(Debug, Event)]
 pub struct EquippedUse {
     pub entity: Entity,
     pub at: Vec2,
+    /// The server tick the shooter was rendering, hits are evaluated against it
+    pub render_tick: u64,
+}
+
+#[derive(Debug, Event)]
+pub struct EquippedReload {
+    pub entity: Entity,
+}
+
+#[derive(Debug, Event)]
+pub struct EquippedChange {
+    pub entity: Entity,
+    /// The inventory slot to put in hand
+    pub slot: usize,
+}
+
+/**
+ *
+ * A Bevy Event to inform client systems
+ * an entity has started reloading its equipment, contains the corresponding
+ * server message
+ */
+#[derive(Event, Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct ReloadEquipmentEvent {
+    pub network_id: NetworkId,
+}
+
+/**
+ *
+ * A Bevy Event to inform client systems
+ * a player has switched to another inventory slot, contains the corresponding
+ * server message
+ */
+#[derive(Event, Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct ChangeEquipmentEvent {
+    pub network_id: NetworkId,
+    pub slot: u8,
+}
+
+/**
+ *
+ * A Bevy Event to inform client systems
+ * the equipment in a player's inventory slot was replaced, contains
+ * the corresponding server message
+ */
+#[derive(Event, Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct EquipEquipmentEvent {
+    pub network_id: NetworkId,
+    pub slot: u8,
+    /// Id of the new equipment in the equipment registry
+    pub equipment: u16,
 }
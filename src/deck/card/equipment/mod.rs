@@ -1,13 +1,21 @@
 use bevy::prelude::*;
 
 use crate::{
+    deck::systems::play_card_system,
     enums::GameState,
-    networking::{is_client, is_server},
+    networking::{is_client, is_server, sets::SimulationSet},
 };
 
 use self::{
-    events::EquippedUse,
-    systems::{equipment_use_system, tick_equipment_system},
+    events::{
+        ChangeEquipmentEvent, EquipEquipmentEvent, EquippedChange, EquippedReload, EquippedUse,
+        ReloadEquipmentEvent,
+    },
+    systems::{
+        auto_reload_system, change_equipment_system, equip_card_system, equipment_reload_system,
+        equipment_use_system, finish_reload_system, on_change_equipment, on_equip_equipment,
+        on_reload_equipment, tick_equipment_system,
+    },
 };
 
 pub mod components;
@@ -19,19 +27,50 @@ pub struct EquipmentPlugin;
 impl Plugin for EquipmentPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
-            Update,
-            (equipment_use_system, tick_equipment_system)
+            FixedUpdate,
+            (
+                equipment_use_system,
+                auto_reload_system,
+                equipment_reload_system,
+                tick_equipment_system,
+                finish_reload_system,
+            )
+                .in_set(SimulationSet::Gameplay)
+                .run_if(is_server())
+                .run_if(in_state(GameState::Gameloop)),
+        );
+
+        app.add_systems(
+            FixedUpdate,
+            (tick_equipment_system, finish_reload_system)
+                .in_set(SimulationSet::Gameplay)
+                .run_if(is_client())
+                .run_if(in_state(GameState::Gameloop)),
+        );
+
+        app.add_systems(
+            FixedUpdate,
+            (
+                change_equipment_system,
+                equip_card_system.after(play_card_system),
+            )
+                .in_set(SimulationSet::Gameplay)
                 .run_if(is_server())
                 .run_if(in_state(GameState::Gameloop)),
         );
 
         app.add_systems(
             Update,
-            (tick_equipment_system)
+            (on_reload_equipment, on_change_equipment, on_equip_equipment)
                 .run_if(is_client())
                 .run_if(in_state(GameState::Gameloop)),
         );
 
         app.add_event::<EquippedUse>();
+        app.add_event::<EquippedReload>();
+        app.add_event::<EquippedChange>();
+        app.add_event::<ReloadEquipmentEvent>();
+        app.add_event::<ChangeEquipmentEvent>();
+        app.add_event::<EquipEquipmentEvent>();
     }
 }
@@ -1,115 +1,434 @@
 use bevy::{
     math::Vec2,
-    prelude::{Children, Commands, EventReader, Quat, Query, Res, ResMut, Transform},
+    prelude::{
+        Assets, Commands, Entity, EventReader, EventWriter, Handle, Parent, Quat, Query, Res,
+        ResMut, Transform, Visibility, Without,
+    },
+    sprite::TextureAtlas,
     time::Time,
 };
 use bevy_2d_collisions::components::CollisionGroup;
-use bevy_renet::renet::RenetServer;
 
 use crate::{
-    asset::resources::AssetHandler,
+    animation::{components::Animator, events::PlayAnimationEvent},
+    asset::{
+        params::{EquipmentAssets, SpriteAssets},
+        resources::{AssetsConfig, CardEffect},
+    },
     deck::{
         card::equipment::components::Equipped,
+        events::CardPlayedEvent,
         keyword::{
-            components::{Damage, ServerProjectileBundle},
+            components::{Keyword, Keywords, LagCompensated, Owner, Range, ServerProjectileBundle},
             events::SpawnProjectileEvent,
         },
     },
-    enums::CollisionGroups,
-    networking::{channels::ServerChannel, networking::ServerMessages},
-    player::components::Team,
+    enums::{CollisionGroups, EntityState},
+    networking::{components::NetworkId, networking::ServerMessages, resources::NetworkRegistry},
+    player::components::{Death, Player, Team},
+    server::params::ServerMessenger,
 };
 
-use super::events::EquippedUse;
+use super::{
+    components::{Equipment, Inventory},
+    events::{
+        ChangeEquipmentEvent, EquipEquipmentEvent, EquippedChange, EquippedReload, EquippedUse,
+        ReloadEquipmentEvent,
+    },
+};
 
 pub fn equipment_use_system(
     mut reader_equippable_use: EventReader<EquippedUse>,
     mut query: Query<&mut Equipped>,
-    mut server: ResMut<RenetServer>,
+    mut server_messenger: ServerMessenger,
     mut command: Commands,
-    transform_query: Query<&Transform>,
-    equipped_children_query: Query<(&Children, &Team)>,
-    asset_handler: Res<AssetHandler>,
+    inventory_query: Query<(&Inventory, &Team, &Transform, &Player), Without<Death>>,
+    sprite_assets: SpriteAssets,
+    mut network_registry: ResMut<NetworkRegistry>,
 ) {
     reader_equippable_use.read().for_each(|equippable_use| {
-        if let Ok((children, team)) = equipped_children_query.get(equippable_use.entity) {
-            for &child in children.iter() {
-                if let Ok(mut equipped) = query.get_mut(child) {
-                    if !equipped.equipment.fire_rate_timer.finished() {
-                        return;
-                    }
+        if let Ok((inventory, team, transform, player)) = inventory_query.get(equippable_use.entity)
+        {
+            // spectators watch without playing
+            if !team.is_playing() {
+                return;
+            }
 
-                    if !equipped.equipment.reload_timer.finished() {
-                        return;
-                    }
+            // nothing is fired while switching slots
+            if inventory.is_swapping() {
+                return;
+            }
+
+            if let Some(Ok(mut equipped)) = inventory
+                .active_equipment()
+                .map(|equipment| query.get_mut(equipment))
+            {
+                if !equipped.equipment.fire_rate_timer.finished() {
+                    return;
+                }
 
-                    if equipped.equipment.empty() {
+                if !equipped.equipment.reload_timer.finished() {
+                    return;
+                }
+
+                if equipped.equipment.empty() {
+                    return;
+                }
+
+                let spawn_point = transform.clone();
+                let velocities = equipped
+                    .equipment
+                    .use_equipment(&spawn_point.translation, &equippable_use.at);
+
+                let mask = if equipped.equipment.projectile_mask == CollisionGroups::Enemy as u32 {
+                    team.enemy_mask()
+                } else if equipped.equipment.projectile_mask == CollisionGroups::Teammate as u32 {
+                    team.ally_mask()
+                } else {
+                    equipped.equipment.projectile_mask
+                };
+                let layer = equipped.equipment.projectile_layer;
+
+                let (projectile_type, (_texture, _animations, hitbox_config)) = match sprite_assets
+                    .id(&equipped.equipment.projectile_type)
+                    .and_then(|id| sprite_assets.get_by_id(id).map(|sprite| (id, sprite)))
+                {
+                    Ok(projectile) => projectile,
+                    Err(error) => {
+                        println!(
+                            "Could not fire {}: {}.",
+                            equipped.equipment.equipment_type, error
+                        );
                         return;
                     }
+                };
 
-                    if let Ok(transform) = transform_query.get(equippable_use.entity) {
-                        let spawn_point = transform.clone();
-                        let velocity = equipped
-                            .equipment
-                            .use_equipment(&spawn_point.translation, &equippable_use.at);
-
-                        let mask = if equipped.equipment.projectile_mask
-                            == CollisionGroups::Enemy as u32
-                        {
-                            (*team).enemy_teams()
-                        } else if equipped.equipment.projectile_mask
-                            == CollisionGroups::Teammate as u32
-                        {
-                            (**team) as u32
-                        } else {
-                            equipped.equipment.projectile_mask
-                        };
-                        let layer = equipped.equipment.projectile_layer;
-
-                        let event = SpawnProjectileEvent {
-                            translation: spawn_point.translation.into(),
-                            velocity: velocity.vector.into(),
-                            projectile_type: equipped.equipment.projectile_type.into(),
-                            layer,
-                            mask,
-                        };
-                        let message: Vec<u8> =
-                            bincode::serialize(&ServerMessages::SpawnProjectile(event))
-                                .expect("Could not serialize spawn projectile message.");
-
-                        let (_texture, _animations, hitbox_config) = asset_handler
-                            .textures
-                            .get(&event.projectile_type.into())
-                            .expect("Could not find projectile texture in asset handler.");
-
-                        let mut transform = Transform::from_translation(spawn_point.translation);
-                        transform.rotation = Quat::from_rotation_z(velocity.rotation);
-
-                        let hitbox_config =
-                            hitbox_config.expect("Could not find hitbox config for bullet.");
-
-                        let mut projectile = ServerProjectileBundle::new(
-                            transform,
-                            velocity,
-                            Vec2::new(hitbox_config.width, hitbox_config.height),
-                            CollisionGroup { layer, mask },
-                        );
+                let hitbox_config =
+                    hitbox_config.expect("Could not find hitbox config for bullet.");
 
-                        projectile.damage = Damage(equipped.equipment.damage as f32);
+                // each projectile in the shot is spawned and sent individually
+                for velocity in velocities {
+                    let mut transform = Transform::from_translation(spawn_point.translation);
+                    transform.rotation = Quat::from_rotation_z(velocity.rotation);
+                    let velocity_vector = velocity.vector;
 
-                        command.spawn(projectile);
+                    let mut projectile = ServerProjectileBundle::new(
+                        transform,
+                        velocity,
+                        Vec2::new(hitbox_config.width, hitbox_config.height),
+                        CollisionGroup { layer, mask },
+                    );
 
-                        server.broadcast_message(ServerChannel::ServerMessages, message);
-                    }
+                    projectile.keywords = Keywords::new(
+                        std::iter::once(Keyword::Damage(equipped.equipment.damage as f32))
+                            .chain(equipped.equipment.keywords.iter().copied())
+                            .collect(),
+                    );
+                    projectile.range = Range::new(equipped.equipment.range as f32);
+                    projectile.owner = Owner(player.id);
+
+                    let network_id = network_registry.allocate();
+                    let projectile_entity = command
+                        .spawn((
+                            projectile,
+                            network_id,
+                            LagCompensated::new(equippable_use.render_tick),
+                        ))
+                        .id();
+                    network_registry.insert(network_id, projectile_entity);
+
+                    let event = SpawnProjectileEvent {
+                        network_id,
+                        translation: spawn_point.translation.into(),
+                        velocity: velocity_vector.into(),
+                        projectile_type,
+                        layer,
+                        mask,
+                    };
+                    server_messenger.broadcast(ServerMessages::SpawnProjectile(event));
                 }
             }
         }
     });
 }
 
-pub fn tick_equipment_system(dt: Res<Time>, mut query: Query<&mut Equipped>) {
+pub fn tick_equipment_system(
+    dt: Res<Time>,
+    mut query: Query<&mut Equipped>,
+    mut inventory_query: Query<&mut Inventory>,
+) {
     query.iter_mut().for_each(|mut equipped| {
         equipped.equipment.fire_rate_timer.tick(dt.delta());
         equipped.equipment.reload_timer.tick(dt.delta());
     });
+
+    inventory_query.iter_mut().for_each(|mut inventory| {
+        inventory.swap_timer.tick(dt.delta());
+    });
+}
+
+pub fn auto_reload_system(
+    mut writer_equipped_reload: EventWriter<EquippedReload>,
+    query: Query<&Equipped>,
+    inventory_query: Query<(Entity, &Inventory)>,
+) {
+    for (entity, inventory) in &inventory_query {
+        let Some(Ok(equipped)) = inventory
+            .active_equipment()
+            .map(|equipment| query.get(equipment))
+        else {
+            continue;
+        };
+
+        if equipped.equipment.empty() && !equipped.equipment.reloading {
+            writer_equipped_reload.send(EquippedReload { entity });
+        }
+    }
+}
+
+pub fn equipment_reload_system(
+    mut reader_equipped_reload: EventReader<EquippedReload>,
+    mut query: Query<&mut Equipped>,
+    mut player_query: Query<(&Inventory, &mut EntityState, &NetworkId), Without<Death>>,
+    mut server_messenger: ServerMessenger,
+) {
+    for equipped_reload in reader_equipped_reload.read() {
+        if let Ok((inventory, mut entity_state, network_id)) =
+            player_query.get_mut(equipped_reload.entity)
+        {
+            if let Some(Ok(mut equipped)) = inventory
+                .active_equipment()
+                .map(|equipment| query.get_mut(equipment))
+            {
+                if !equipped.equipment.can_reload() {
+                    continue;
+                }
+
+                equipped.equipment.start_reload();
+                *entity_state = EntityState::Reload;
+
+                server_messenger.broadcast(ServerMessages::ReloadEquipment(ReloadEquipmentEvent {
+                    network_id: *network_id,
+                }));
+            }
+        }
+    }
+}
+
+pub fn on_reload_equipment(
+    mut reader_reload_equipment: EventReader<ReloadEquipmentEvent>,
+    mut writer_play_animation: EventWriter<PlayAnimationEvent>,
+    mut query: Query<&mut Equipped>,
+    inventory_query: Query<&Inventory>,
+    network_registry: Res<NetworkRegistry>,
+) {
+    for reload_equipment in reader_reload_equipment.read() {
+        let Some(entity) = network_registry.entity(&reload_equipment.network_id) else {
+            continue;
+        };
+
+        let Some(active) = inventory_query
+            .get(entity)
+            .ok()
+            .and_then(Inventory::active_equipment)
+        else {
+            continue;
+        };
+
+        if let Ok(mut equipped) = query.get_mut(active) {
+            equipped.equipment.start_reload();
+            writer_play_animation.send(PlayAnimationEvent::new(active, "Reload"));
+        }
+    }
+}
+
+pub fn finish_reload_system(
+    mut writer_play_animation: EventWriter<PlayAnimationEvent>,
+    mut query: Query<(&mut Equipped, &Parent, Entity)>,
+    mut state_query: Query<&mut EntityState>,
+) {
+    for (mut equipped, parent, entity) in &mut query {
+        if !equipped.equipment.reloading || !equipped.equipment.reload_timer.finished() {
+            continue;
+        }
+
+        equipped.equipment.reload();
+        writer_play_animation.send(PlayAnimationEvent::new(entity, "Idle"));
+
+        if let Ok(mut entity_state) = state_query.get_mut(parent.get()) {
+            if *entity_state == EntityState::Reload {
+                *entity_state = EntityState::Idle;
+            }
+        }
+    }
+}
+
+/// Puts the inventory slot players asked for in hand, a reload in progress is cancelled
+pub fn change_equipment_system(
+    mut reader_equipped_change: EventReader<EquippedChange>,
+    mut server_messenger: ServerMessenger,
+    asset_config: Res<AssetsConfig>,
+    mut player_query: Query<(&mut Inventory, &mut EntityState, &Team, &NetworkId), Without<Death>>,
+    mut query: Query<&mut Equipped>,
+) {
+    for equipped_change in reader_equipped_change.read() {
+        let slot = equipped_change.slot;
+        let Ok((mut inventory, mut entity_state, team, network_id)) =
+            player_query.get_mut(equipped_change.entity)
+        else {
+            continue;
+        };
+
+        if !team.is_playing() || !inventory.can_switch(slot) {
+            continue;
+        }
+
+        if let Some(Ok(mut equipped)) = inventory
+            .active_equipment()
+            .map(|equipment| query.get_mut(equipment))
+        {
+            equipped.equipment.cancel_reload();
+        }
+        if *entity_state == EntityState::Reload {
+            *entity_state = EntityState::Idle;
+        }
+
+        inventory.switch(slot, asset_config.inventory.swap_delay);
+
+        server_messenger.broadcast(ServerMessages::ChangeEquipment(ChangeEquipmentEvent {
+            network_id: *network_id,
+            slot: slot as u8,
+        }));
+    }
+}
+
+pub fn on_change_equipment(
+    mut reader_change_equipment: EventReader<ChangeEquipmentEvent>,
+    mut writer_play_animation: EventWriter<PlayAnimationEvent>,
+    mut inventory_query: Query<&mut Inventory>,
+    mut query: Query<(&mut Equipped, &mut Visibility)>,
+    network_registry: Res<NetworkRegistry>,
+    asset_config: Res<AssetsConfig>,
+) {
+    for change_equipment in reader_change_equipment.read() {
+        let Some(entity) = network_registry.entity(&change_equipment.network_id) else {
+            continue;
+        };
+        let Ok(mut inventory) = inventory_query.get_mut(entity) else {
+            continue;
+        };
+
+        let slot = change_equipment.slot as usize;
+        if !inventory.can_switch(slot) {
+            continue;
+        }
+
+        if let Some(active) = inventory.active_equipment() {
+            if let Ok((mut equipped, _)) = query.get_mut(active) {
+                equipped.equipment.cancel_reload();
+                writer_play_animation.send(PlayAnimationEvent::new(active, "Idle"));
+            }
+        }
+
+        inventory.switch(slot, asset_config.inventory.swap_delay);
+
+        // only the equipment in hand is shown
+        for (index, &equipment) in inventory.slots.iter().enumerate() {
+            if let Ok((_, mut visibility)) = query.get_mut(equipment) {
+                *visibility = if index == slot {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+            }
+        }
+    }
+}
+
+/// Replaces the equipment in hand with the one on a played equipment card,
+/// the new equipment needs the swap delay before it can be fired
+pub fn equip_card_system(
+    mut reader_card_played: EventReader<CardPlayedEvent>,
+    mut server_messenger: ServerMessenger,
+    equipment_assets: EquipmentAssets,
+    asset_config: Res<AssetsConfig>,
+    mut inventory_query: Query<(&mut Inventory, &NetworkId)>,
+    mut query: Query<&mut Equipped>,
+) {
+    for card_played in reader_card_played.read() {
+        let CardEffect::Equipment(equipment) = &card_played.effect else {
+            continue;
+        };
+
+        let (stats, equipment_id) = match equipment_assets
+            .get(equipment)
+            .and_then(|stats| Ok((stats, equipment_assets.id(equipment)?)))
+        {
+            Ok(equipment) => equipment,
+            Err(error) => {
+                println!("Could not equip {}: {}.", card_played.card, error);
+                continue;
+            }
+        };
+
+        let Ok((mut inventory, network_id)) = inventory_query.get_mut(card_played.player) else {
+            continue;
+        };
+        let Some(Ok(mut equipped)) = inventory
+            .active_equipment()
+            .map(|active| query.get_mut(active))
+        else {
+            continue;
+        };
+
+        // the new equipment carries on the spray of the one it replaces
+        let spray_seed = equipped.equipment.spray_seed;
+        equipped.equipment = Equipment::from(stats).seeded(spray_seed);
+        let slot = inventory.active;
+        inventory.switch(slot, asset_config.inventory.swap_delay);
+
+        server_messenger.broadcast(ServerMessages::EquipEquipment(EquipEquipmentEvent {
+            network_id: *network_id,
+            slot: slot as u8,
+            equipment: equipment_id,
+        }));
+    }
+}
+
+pub fn on_equip_equipment(
+    mut reader_equip_equipment: EventReader<EquipEquipmentEvent>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    equipment_assets: EquipmentAssets,
+    sprite_assets: SpriteAssets,
+    network_registry: Res<NetworkRegistry>,
+    inventory_query: Query<&Inventory>,
+    mut query: Query<(&mut Equipped, &mut Handle<TextureAtlas>, &mut Animator)>,
+) {
+    for equip_equipment in reader_equip_equipment.read() {
+        // ids come from the network, an unknown one keeps the current equipment
+        let (stats, (texture, animations, _hitbox_config)) = match equipment_assets
+            .get_by_id(equip_equipment.equipment)
+            .and_then(|stats| Ok((stats, sprite_assets.get(&stats.sprite)?)))
+        {
+            Ok(equipment) => equipment,
+            Err(error) => {
+                println!("Could not equip equipment: {}.", error);
+                continue;
+            }
+        };
+
+        let Some(slot_entity) = network_registry
+            .entity(&equip_equipment.network_id)
+            .and_then(|entity| inventory_query.get(entity).ok())
+            .and_then(|inventory| inventory.slots.get(equip_equipment.slot as usize))
+        else {
+            continue;
+        };
+
+        if let Ok((mut equipped, mut texture_atlas, mut animator)) = query.get_mut(*slot_entity) {
+            equipped.equipment = stats.into();
+            *texture_atlas = texture_atlases.add(texture.clone());
+            *animator = Animator::import(animations);
+        }
+    }
 }
@@ -0,0 +1,79 @@
+use bevy::{
+    prelude::Component,
+    time::{Timer, TimerMode},
+};
+
+use crate::{asset::registry::CardName, math::seeded_index};
+
+/**
+ * Deck
+ *
+ * The cards a player draws from, shuffled when it is dealt
+ * and whenever the discard pile is shuffled back into it
+ */
+#[derive(Component, Debug)]
+pub struct Deck {
+    /// The cards left to draw, the last one is on top
+    pub cards: Vec<CardName>,
+    /// Counts down to the next draw while the hand isn't full
+    pub draw_timer: Timer,
+    shuffle_seed: u64,
+}
+
+impl Deck {
+    pub fn new(cards: Vec<CardName>, draw_interval: f32, shuffle_seed: u64) -> Self {
+        let mut deck = Self {
+            cards,
+            draw_timer: Timer::from_seconds(draw_interval, TimerMode::Repeating),
+            shuffle_seed,
+        };
+        deck.shuffle();
+
+        deck
+    }
+
+    /// Draws the top card, shuffling the discard pile back in once the deck runs out
+    pub fn draw(&mut self, discard_pile: &mut DiscardPile) -> Option<CardName> {
+        if self.cards.is_empty() && !discard_pile.cards.is_empty() {
+            self.cards.append(&mut discard_pile.cards);
+            self.shuffle();
+        }
+
+        self.cards.pop()
+    }
+
+    fn shuffle(&mut self) {
+        for index in (1..self.cards.len()).rev() {
+            let other = seeded_index(self.shuffle_seed, index + 1);
+            self.shuffle_seed = self.shuffle_seed.wrapping_add(1);
+            self.cards.swap(index, other);
+        }
+    }
+}
+
+/**
+ * Hand
+ *
+ * The cards a player can play, by their slot
+ */
+#[derive(Component, Debug, Default)]
+pub struct Hand {
+    pub cards: Vec<CardName>,
+}
+
+impl Hand {
+    /// Takes the card out of a slot, the cards after it move up a slot
+    pub fn play(&mut self, slot: usize) -> Option<CardName> {
+        (slot < self.cards.len()).then(|| self.cards.remove(slot))
+    }
+}
+
+/**
+ * Discard Pile
+ *
+ * The cards a player has played since the deck was last shuffled
+ */
+#[derive(Component, Debug, Default)]
+pub struct DiscardPile {
+    pub cards: Vec<CardName>,
+}
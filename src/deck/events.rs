@@ -0,0 +1,55 @@
+use bevy::prelude::{Entity, Event};
+use bevy_renet::renet::ClientId;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset::{registry::CardName, resources::CardEffect},
+    networking::components::NetworkId,
+};
+
+/**
+ * Play Card Event
+ *
+ * A Bevy Event to inform server systems
+ * a player asked to play the card in a slot of their hand,
+ * it is played once its target is validated
+ */
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayCardEvent {
+    pub player: Entity,
+    pub owner: ClientId,
+    pub slot: u8,
+    pub target: Option<NetworkId>,
+}
+
+/**
+ * Card Played Event
+ *
+ * A Bevy Event to inform server systems
+ * a player played a card, `target` is the player
+ * themselves when the card was played on no one else
+ */
+#[derive(Event, Debug, Clone)]
+pub struct CardPlayedEvent {
+    pub player: Entity,
+    pub owner: ClientId,
+    pub target: Entity,
+    pub card: CardName,
+    pub effect: CardEffect,
+}
+
+/**
+ * Hand Event
+ *
+ * A Bevy Event to inform the client systems of a player
+ * the cards in their hand changed, contains the corresponding
+ * server message sent only to that player
+ */
+#[derive(Event, Debug, Serialize, Deserialize, Clone)]
+pub struct HandEvent {
+    /// Ids of the cards in the card registry, by slot
+    pub cards: Vec<u16>,
+    /// Cards left in the deck
+    pub deck: u16,
+    pub discard_pile: u16,
+}
@@ -1,35 +1,200 @@
 use bevy::prelude::{Deref, DerefMut};
 use bevy::{
     math::Vec2,
-    prelude::{Bundle, Component, Handle, Transform},
+    prelude::{Bundle, Component, Entity, Handle, Transform},
     sprite::{SpriteSheetBundle, TextureAtlas, TextureAtlasSprite},
 };
 use bevy_2d_collisions::components::{CollisionBox, CollisionBundle, CollisionGroup};
+use bevy_renet::renet::ClientId;
+use serde::{Deserialize, Serialize};
 
 use crate::animation::components::{Animated2DObjectBundle, Animator};
 use crate::body::components::Object2DBundle;
 use crate::physics::components::{AnimatedKineticBodyBundle, KineticBodyBundle, Velocity};
 
-// DAMAGE Keyword Component
-#[derive(Component, Debug, Deref, DerefMut)]
-pub struct Damage(pub f32);
+/**
+ * Keyword
+ *
+ * An effect carried by projectiles and cards, declared in the asset config
+ * and applied to the target they hit or are played on
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Keyword {
+    Damage(f32),
+    /// Deals the hit's damage to the other enemies within the radius
+    Explode(f32),
+    /// Heals the attacker by a fraction of the hit's damage dealt
+    Lifesteal(f32),
+    Heal(f32),
+    /// Pushes the target this far along the hit's direction
+    Knockback(f32),
+    /// Slows the target by a fraction of its speed, for seconds
+    Slow(f32, f32),
+    /// Sets the target on fire, damage per second for seconds
+    Burn(f32, f32),
+    /// Speeds the target up by a fraction of its speed, for seconds
+    Haste(f32, f32),
+    /// Stuns the target for seconds
+    Stun(f32),
+    /// Makes the target invulnerable for seconds
+    Invulnerable(f32),
+    /// Lets a projectile pass through this many targets
+    Pierce(u32),
+}
 
-impl Default for Damage {
-    fn default() -> Self {
-        Self(10.0)
+impl Keyword {
+    /// Position of the keyword in the resolution order, the health keywords
+    /// resolve before the ones controlling the target
+    pub fn order(&self) -> u8 {
+        match self {
+            Keyword::Damage(_) => 0,
+            Keyword::Explode(_) => 1,
+            Keyword::Lifesteal(_) => 2,
+            Keyword::Heal(_) => 3,
+            Keyword::Knockback(_) => 4,
+            Keyword::Slow(..) => 5,
+            Keyword::Burn(..) => 6,
+            Keyword::Haste(..) => 7,
+            Keyword::Stun(_) => 8,
+            Keyword::Invulnerable(_) => 9,
+            Keyword::Pierce(_) => 10,
+        }
+    }
+}
+
+/**
+ * Keywords
+ *
+ * The keywords a projectile applies to what it hits, in resolution order
+ */
+#[derive(Component, Debug, Default, Clone, Deref)]
+pub struct Keywords(Vec<Keyword>);
+
+impl Keywords {
+    pub fn new(mut keywords: Vec<Keyword>) -> Self {
+        keywords.sort_by_key(Keyword::order);
+        Self(keywords)
+    }
+
+    /// The damage of a hit, before any health is taken
+    pub fn damage(&self) -> f32 {
+        self.iter()
+            .map(|keyword| match keyword {
+                Keyword::Damage(damage) => *damage,
+                _ => 0.0,
+            })
+            .sum()
+    }
+
+    /// The fraction of a hit's damage dealt that heals the attacker
+    pub fn lifesteal(&self) -> f32 {
+        self.iter()
+            .map(|keyword| match keyword {
+                Keyword::Lifesteal(fraction) => *fraction,
+                _ => 0.0,
+            })
+            .sum()
+    }
+
+    /// How many targets a projectile passes through before it is spent
+    pub fn pierce(&self) -> usize {
+        self.iter()
+            .map(|keyword| match keyword {
+                Keyword::Pierce(pierce) => *pierce as usize,
+                _ => 0,
+            })
+            .sum()
     }
 }
 
+/**
+ * Hit Targets
+ *
+ * The entities a projectile has already hit, it never hits one twice
+ */
+#[derive(Component, Debug, Default, Deref, DerefMut)]
+pub struct HitTargets(pub Vec<Entity>);
+
 #[derive(Component, Debug, Default)]
 pub struct Projectile;
 
+/**
+ * Owner
+ *
+ * The client whose player fired a projectile,
+ * credited with the damage and kills it deals
+ */
+#[derive(Component, Debug, Clone, Copy, Deref)]
+pub struct Owner(pub ClientId);
+
+impl Default for Owner {
+    fn default() -> Self {
+        Self(ClientId::from_raw(0))
+    }
+}
+
+/**
+ * Lag Compensated
+ *
+ * A projectile whose hits are evaluated against players as the
+ * shooter saw them, rewound to the tick the client was rendering
+ */
+#[derive(Component, Debug, Default)]
+pub struct LagCompensated {
+    /// The server tick the client was rendering when it fired
+    pub render_tick: u64,
+    /// Ticks the projectile has been in flight
+    pub age: u64,
+}
+
+impl LagCompensated {
+    pub fn new(render_tick: u64) -> Self {
+        Self {
+            render_tick,
+            age: 0,
+        }
+    }
+}
+
+/**
+ * Range
+ *
+ * How far a projectile may travel before it is despawned,
+ * and how far it has travelled so far
+ */
+#[derive(Component, Debug)]
+pub struct Range {
+    pub max: f32,
+    pub travelled: f32,
+}
+
+impl Default for Range {
+    fn default() -> Self {
+        Self {
+            max: f32::MAX,
+            travelled: 0.0,
+        }
+    }
+}
+
+impl Range {
+    pub fn new(max: f32) -> Self {
+        Self {
+            max,
+            ..Default::default()
+        }
+    }
+
+    pub fn exceeded(&self) -> bool {
+        self.travelled >= self.max
+    }
+}
+
 #[derive(Bundle, Default)]
 pub struct ProjectileBundle {
     pub projectile: Projectile,
 
     pub kinetic_body: AnimatedKineticBodyBundle,
-
-    pub damage: Damage,
 }
 
 impl ProjectileBundle {
@@ -70,7 +235,6 @@ impl ProjectileBundle {
                     ..Default::default()
                 },
             },
-            ..Default::default()
         }
     }
 }
@@ -81,7 +245,13 @@ pub struct ServerProjectileBundle {
 
     pub kinetic_body: KineticBodyBundle,
 
-    pub damage: Damage,
+    pub keywords: Keywords,
+
+    pub hit_targets: HitTargets,
+
+    pub range: Range,
+
+    pub owner: Owner,
 }
 
 impl ServerProjectileBundle {
@@ -1,6 +1,11 @@
-use bevy::prelude::{Entity, Event};
+use bevy::prelude::{Entity, Event, Vec2};
+use bevy_renet::renet::ClientId;
 use serde::{Deserialize, Serialize};
 
+use crate::networking::components::NetworkId;
+
+use super::components::Keywords;
+
 /**
  *
  * A Bevy Event to inform client systems
@@ -9,7 +14,7 @@ use serde::{Deserialize, Serialize};
  */
 #[derive(Event, Debug, Serialize, Deserialize)]
 pub struct DamageEntityEvent {
-    pub entity: Entity,
+    pub network_id: NetworkId,
     pub damage: f32,
 }
 
@@ -21,9 +26,119 @@ pub struct DamageEntityEvent {
  */
 #[derive(Event, Debug, Serialize, Deserialize, Clone, Copy)]
 pub struct SpawnProjectileEvent {
+    pub network_id: NetworkId,
     pub translation: [f32; 3],
     pub velocity: [f32; 2],
-    pub projectile_type: u8,
+    /// Id of the projectile's sprite in the asset registry
+    pub projectile_type: u16,
     pub layer: u32,
     pub mask: u32,
 }
+
+/**
+ *
+ * A Bevy Event to inform systems
+ * a projectile should be despawned, contains the corresponding
+ * server message
+ */
+#[derive(Event, Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct DespawnProjectileEvent {
+    pub network_id: NetworkId,
+}
+
+/**
+ * Projectile Hit Event
+ *
+ * A Bevy Event to inform server systems
+ * a projectile has hit an entity
+ */
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ProjectileHitEvent {
+    pub projectile: Entity,
+    pub target: Entity,
+}
+
+/**
+ * Inflict Damage Event
+ *
+ * A Bevy Event to inform server systems
+ * an entity should take damage, from a projectile hit
+ * or a played card owned by `attacker`
+ */
+#[derive(Event, Debug, Clone, Copy)]
+pub struct InflictDamageEvent {
+    pub target: Entity,
+    pub attacker: Option<ClientId>,
+    pub damage: f32,
+    /// Fraction of the damage dealt that heals the attacker
+    pub lifesteal: f32,
+}
+
+/**
+ * Entity Damaged Event
+ *
+ * A Bevy Event to inform server systems
+ * an entity was damaged, `damage` is the health
+ * actually taken and `attacker` the owner of the damage
+ */
+#[derive(Event, Debug, Clone, Copy)]
+pub struct EntityDamagedEvent {
+    pub target: Entity,
+    pub attacker: Option<ClientId>,
+    pub damage: f32,
+    pub lethal: bool,
+    pub lifesteal: f32,
+}
+
+/**
+ * Keyword Hit Event
+ *
+ * A Bevy Event to inform server systems
+ * keywords landed on a target, from a projectile hit or a played card,
+ * `keywords` are in resolution order and `mask` holds the layers
+ * the attacker may affect
+ */
+#[derive(Event, Debug, Clone)]
+pub struct KeywordHitEvent {
+    pub target: Entity,
+    pub attacker: Option<ClientId>,
+    pub at: Vec2,
+    pub direction: Vec2,
+    pub mask: u32,
+    pub keywords: Keywords,
+}
+
+/**
+ * Restore Health Event
+ *
+ * A Bevy Event to inform server systems
+ * an entity should be healed
+ */
+#[derive(Event, Debug, Clone, Copy)]
+pub struct RestoreHealthEvent {
+    pub target: Entity,
+    pub amount: f32,
+}
+
+/**
+ * Keyword Outcome Event
+ *
+ * A Bevy Event to inform client systems
+ * a keyword changed the world, contains the corresponding
+ * server message
+ */
+#[derive(Event, Debug, Serialize, Deserialize, Clone, Copy)]
+pub enum KeywordOutcomeEvent {
+    Heal {
+        network_id: NetworkId,
+        amount: f32,
+    },
+    Knockback {
+        network_id: NetworkId,
+        translation: [f32; 3],
+    },
+    Explode {
+        translation: [f32; 3],
+        radius: f32,
+    },
+}
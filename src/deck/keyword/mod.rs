@@ -1,13 +1,24 @@
 use bevy::prelude::*;
 
 use crate::{
+    deck::systems::play_card_system,
     enums::GameState,
-    networking::{is_client, is_server},
+    networking::{is_client, is_server, sets::SimulationSet},
 };
 
 use self::{
-    events::{DamageEntityEvent, SpawnProjectileEvent},
-    systems::{damage_collision, on_damage_entity, spawn_projectile},
+    events::{
+        DamageEntityEvent, DespawnProjectileEvent, EntityDamagedEvent, InflictDamageEvent,
+        KeywordHitEvent, KeywordOutcomeEvent, ProjectileHitEvent, RestoreHealthEvent,
+        SpawnProjectileEvent,
+    },
+    systems::{
+        burn_system, despawn_projectile, inflict_damage_system, keyword_card_system,
+        kill_entity_system, lag_compensated_collision_system, lifesteal_system, on_damage_entity,
+        on_despawn_projectile, on_keyword_outcome, projectile_collision_system,
+        projectile_hit_system, projectile_range_system, resolve_control_keywords,
+        resolve_health_keywords, restore_health_system, spawn_projectile,
+    },
 };
 
 pub mod components;
@@ -19,20 +30,58 @@ pub struct KeywordPlugin;
 impl Plugin for KeywordPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
-            Update,
-            (damage_collision)
+            FixedUpdate,
+            (
+                (
+                    projectile_collision_system,
+                    lag_compensated_collision_system,
+                )
+                    .before(projectile_hit_system),
+                projectile_hit_system,
+                keyword_card_system.after(play_card_system),
+                burn_system,
+                // health keywords resolve first, then the hit's damage and heals
+                // are applied, then the keywords controlling the survivors
+                resolve_health_keywords
+                    .after(projectile_hit_system)
+                    .after(keyword_card_system),
+                inflict_damage_system
+                    .after(resolve_health_keywords)
+                    .after(burn_system),
+                lifesteal_system.after(inflict_damage_system),
+                restore_health_system.after(lifesteal_system),
+                resolve_control_keywords.after(restore_health_system),
+                kill_entity_system.after(inflict_damage_system),
+                projectile_range_system,
+                despawn_projectile
+                    .after(projectile_hit_system)
+                    .after(projectile_range_system),
+            )
+                .in_set(SimulationSet::Gameplay)
                 .run_if(is_server())
                 .run_if(in_state(GameState::Gameloop)),
         );
 
         app.add_systems(
             Update,
-            (on_damage_entity, spawn_projectile)
+            (
+                on_damage_entity,
+                on_keyword_outcome,
+                spawn_projectile,
+                on_despawn_projectile.after(spawn_projectile),
+            )
                 .run_if(is_client())
                 .run_if(in_state(GameState::Gameloop)),
         );
 
         app.add_event::<SpawnProjectileEvent>();
         app.add_event::<DamageEntityEvent>();
+        app.add_event::<DespawnProjectileEvent>();
+        app.add_event::<ProjectileHitEvent>();
+        app.add_event::<InflictDamageEvent>();
+        app.add_event::<EntityDamagedEvent>();
+        app.add_event::<KeywordHitEvent>();
+        app.add_event::<RestoreHealthEvent>();
+        app.add_event::<KeywordOutcomeEvent>();
     }
 }
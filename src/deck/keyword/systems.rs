@@ -1,90 +1,510 @@
 use bevy::{
     asset::Assets,
     ecs::{
+        entity::Entity,
         event::{EventReader, EventWriter},
+        query::{Has, With, Without},
         system::{Commands, Query, Res, ResMut},
     },
     math::{Quat, Vec2},
-    sprite::TextureAtlas,
+    sprite::{collide_aabb::collide, TextureAtlas},
+    time::{Fixed, Time},
     transform::components::Transform,
 };
-use bevy_2d_collisions::{components::CollisionGroup, events::CollisionBegin};
-use bevy_renet::renet::RenetServer;
+use bevy_2d_collisions::{
+    components::{CollisionBox, CollisionGroup},
+    events::CollisionBegin,
+};
 
 use crate::{
     animation::components::Animator,
-    asset::resources::AssetHandler,
-    client::resources::NetworkEntities,
+    asset::{params::SpriteAssets, resources::CardEffect},
+    deck::events::CardPlayedEvent,
     enums::EntityState,
-    networking::{channels::ServerChannel, networking::ServerMessages},
+    networking::{components::NetworkId, networking::ServerMessages, resources::NetworkRegistry},
     physics::components::Velocity,
-    player::components::Death,
-    server::events::SyncEntityEvent,
-    stats::components::Health,
+    player::components::{Death, HitboxHistory, Player, SpawnProtection, Team},
+    server::{
+        events::SyncEntityEvent,
+        params::ServerMessenger,
+        resources::{LagCompensation, ServerLobby, ServerTick},
+    },
+    stats::{
+        components::{Health, StatusEffects, StatusKind},
+        events::{ApplyStatusEvent, StatusTickEvent},
+    },
 };
 
 use super::{
-    components::{Damage, ProjectileBundle},
-    events::{DamageEntityEvent, SpawnProjectileEvent},
+    components::{
+        HitTargets, Keyword, Keywords, LagCompensated, Owner, Projectile, ProjectileBundle, Range,
+    },
+    events::{
+        DamageEntityEvent, DespawnProjectileEvent, EntityDamagedEvent, InflictDamageEvent,
+        KeywordHitEvent, KeywordOutcomeEvent, ProjectileHitEvent, RestoreHealthEvent,
+        SpawnProjectileEvent,
+    },
 };
 
-pub fn damage_collision(
-    mut events: EventReader<CollisionBegin>,
-    mut server: ResMut<RenetServer>,
-    mut writer_sync_entity: EventWriter<SyncEntityEvent>,
-    mut p_query: Query<(&mut Health, &mut EntityState)>,
-    mut command: Commands,
-    dmg_query: Query<&Damage>,
+/// Whether a projectile hit the player that fired it, which it passes through
+fn is_own_hit(owner: Option<&Owner>, target: Option<&Player>) -> bool {
+    matches!((owner, target), (Some(owner), Some(target)) if **owner == target.id)
+}
+
+/// Turns the collisions of projectiles that are not lag compensated into hits
+pub fn projectile_collision_system(
+    mut reader_collision_begin: EventReader<CollisionBegin>,
+    mut writer_projectile_hit: EventWriter<ProjectileHitEvent>,
+    query: Query<Option<&Owner>, (With<Projectile>, Without<LagCompensated>)>,
+    player_query: Query<&Player>,
+) {
+    for collision_begin in reader_collision_begin.read() {
+        let Ok(owner) = query.get(collision_begin.entity) else {
+            continue;
+        };
+
+        if !is_own_hit(owner, player_query.get(collision_begin.detected).ok()) {
+            writer_projectile_hit.send(ProjectileHitEvent {
+                projectile: collision_begin.entity,
+                target: collision_begin.detected,
+            });
+        }
+    }
+}
+
+/// Detects the hits of lag compensated projectiles against the hitboxes
+/// players had on the tick the shooter saw, capped by the maximum rewind
+pub fn lag_compensated_collision_system(
+    mut writer_projectile_hit: EventWriter<ProjectileHitEvent>,
+    mut projectile_query: Query<(
+        Entity,
+        &Transform,
+        &CollisionBox,
+        &CollisionGroup,
+        &mut LagCompensated,
+    )>,
+    target_query: Query<(Entity, &CollisionGroup, &HitboxHistory, &Player)>,
+    owner_query: Query<&Owner>,
+    server_tick: Res<ServerTick>,
+    lag_compensation: Res<LagCompensation>,
+    fixed_time: Res<Time<Fixed>>,
+) {
+    let max_rewind = lag_compensation.max_rewind_ticks(fixed_time.timestep());
+    let oldest_tick = server_tick.saturating_sub(max_rewind);
+
+    for (projectile, transform, collision_box, collision_group, mut lag_compensated) in
+        &mut projectile_query
+    {
+        // the projectile travels through the past at the pace it was fired in
+        let tick =
+            (lag_compensated.render_tick + lag_compensated.age).clamp(oldest_tick, **server_tick);
+        lag_compensated.age += 1;
+
+        // every overlapping hitbox is reported, the hit system skips the targets
+        // that can't be hit so they don't shield the ones that can
+        let owner = owner_query.get(projectile).ok();
+        let hits = target_query
+            .iter()
+            .filter(|(_, target_group, _, player)| {
+                collision_group.can_see(target_group) && !is_own_hit(owner, Some(player))
+            })
+            .filter_map(|(target, _, hitbox_history, _)| {
+                hitbox_history.at(tick).map(|sample| (target, sample))
+            })
+            .filter(|(_, sample)| {
+                collide(
+                    transform.translation,
+                    collision_box.size,
+                    sample.translation.extend(0.0),
+                    sample.size,
+                )
+                .is_some()
+            });
+
+        for (target, _) in hits {
+            writer_projectile_hit.send(ProjectileHitEvent { projectile, target });
+        }
+    }
+}
+
+/// Turns projectile hits into keyword hits, a projectile passes through
+/// as many targets as its pierce allows and never hits the same one twice
+pub fn projectile_hit_system(
+    mut events: EventReader<ProjectileHitEvent>,
+    mut writer_keyword_hit: EventWriter<KeywordHitEvent>,
+    mut writer_despawn_projectile: EventWriter<DespawnProjectileEvent>,
+    target_query: Query<Has<SpawnProtection>, (With<Health>, Without<Death>)>,
+    mut projectile_query: Query<(&Keywords, &mut HitTargets, &NetworkId, Option<&Owner>)>,
+    body_query: Query<(&Transform, &Velocity, &CollisionGroup)>,
 ) {
     for event in events.read() {
-        let dmg = dmg_query.get(event.entity);
-        let damagable_result = p_query.get_mut(event.detected);
+        let Ok((keywords, mut hit_targets, projectile_network_id, owner)) =
+            projectile_query.get_mut(event.projectile)
+        else {
+            continue;
+        };
+        let Ok((transform, velocity, collision_group)) = body_query.get(event.projectile) else {
+            continue;
+        };
+        let Ok(spawn_protected) = target_query.get(event.target) else {
+            continue;
+        };
 
-        if dmg.is_err() {
+        // a projectile can begin colliding with several entities in the same frame,
+        // but it lands no more hits once spent, and freshly spawned players
+        // let projectiles pass through
+        if hit_targets.len() > keywords.pierce()
+            || hit_targets.contains(&event.target)
+            || spawn_protected
+        {
             continue;
         }
 
-        if damagable_result.is_err() {
-            continue;
+        writer_keyword_hit.send(KeywordHitEvent {
+            target: event.target,
+            attacker: owner.map(|owner| **owner),
+            at: transform.translation.truncate(),
+            direction: velocity.vector.normalize_or_zero(),
+            mask: collision_group.mask,
+            keywords: keywords.clone(),
+        });
+
+        hit_targets.push(event.target);
+        if hit_targets.len() > keywords.pierce() {
+            writer_despawn_projectile.send(DespawnProjectileEvent {
+                network_id: *projectile_network_id,
+            });
         }
+    }
+}
+
+/// Resolves the keywords changing health, the damage of the hit
+/// is what explosions spread, lifesteal drains from the damage it deals
+pub fn resolve_health_keywords(
+    mut reader_keyword_hit: EventReader<KeywordHitEvent>,
+    mut writer_inflict_damage: EventWriter<InflictDamageEvent>,
+    mut writer_restore_health: EventWriter<RestoreHealthEvent>,
+    mut server_messenger: ServerMessenger,
+    lobby: Res<ServerLobby>,
+    target_query: Query<(Entity, &Transform, &CollisionGroup, Has<Death>), With<Health>>,
+) {
+    for keyword_hit in reader_keyword_hit.read() {
+        let damage = keyword_hit.keywords.damage();
+        let lifesteal = keyword_hit.keywords.lifesteal();
+        let attacker = keyword_hit
+            .attacker
+            .and_then(|attacker| lobby.players.get(&attacker.raw()).copied());
+
+        for keyword in keyword_hit.keywords.iter() {
+            match *keyword {
+                Keyword::Damage(damage) => writer_inflict_damage.send(InflictDamageEvent {
+                    target: keyword_hit.target,
+                    attacker: keyword_hit.attacker,
+                    damage,
+                    lifesteal,
+                }),
+                Keyword::Explode(radius) => {
+                    // the splash only reaches the layers the hit could reach
+                    let splashed = target_query
+                        .iter()
+                        .filter(|(entity, _, collision_group, dead)| {
+                            !dead
+                                && *entity != keyword_hit.target
+                                && Some(*entity) != attacker
+                                && collision_group.layer & keyword_hit.mask != 0
+                        })
+                        .filter(|(_, transform, ..)| {
+                            transform.translation.truncate().distance(keyword_hit.at) <= radius
+                        });
+
+                    for (entity, ..) in splashed {
+                        writer_inflict_damage.send(InflictDamageEvent {
+                            target: entity,
+                            attacker: keyword_hit.attacker,
+                            damage,
+                            lifesteal: 0.0,
+                        });
+                    }
 
-        let dmg = dmg.unwrap();
-        let (mut health, mut entity_state) = damagable_result.unwrap();
-        health.current -= **dmg;
+                    server_messenger.broadcast(ServerMessages::KeywordOutcome(
+                        KeywordOutcomeEvent::Explode {
+                            translation: keyword_hit.at.extend(0.0).into(),
+                            radius,
+                        },
+                    ));
+                }
+                Keyword::Heal(amount) => writer_restore_health.send(RestoreHealthEvent {
+                    target: keyword_hit.target,
+                    amount,
+                }),
+                _ => {}
+            }
+        }
+    }
+}
 
+/// Resolves the keywords controlling the target, once the hit's damage is applied,
+/// targets killed by the hit are left alone
+pub fn resolve_control_keywords(
+    mut reader_keyword_hit: EventReader<KeywordHitEvent>,
+    mut server_messenger: ServerMessenger,
+    mut writer_sync_entity: EventWriter<SyncEntityEvent>,
+    mut writer_apply_status: EventWriter<ApplyStatusEvent>,
+    lobby: Res<ServerLobby>,
+    mut query: Query<(&mut Transform, &Health, &NetworkId), Without<Death>>,
+) {
+    for keyword_hit in reader_keyword_hit.read() {
+        let Ok((mut transform, health, network_id)) = query.get_mut(keyword_hit.target) else {
+            continue;
+        };
         if health.current <= 0.0 {
+            continue;
+        }
+
+        let source = keyword_hit
+            .attacker
+            .and_then(|attacker| lobby.players.get(&attacker.raw()).copied());
+
+        for keyword in keyword_hit.keywords.iter() {
+            let (kind, magnitude, seconds) = match *keyword {
+                Keyword::Knockback(force) => {
+                    transform.translation += (keyword_hit.direction * force).extend(0.0);
+                    writer_sync_entity.send(SyncEntityEvent {
+                        entity: keyword_hit.target,
+                    });
+                    server_messenger.broadcast(ServerMessages::KeywordOutcome(
+                        KeywordOutcomeEvent::Knockback {
+                            network_id: *network_id,
+                            translation: transform.translation.into(),
+                        },
+                    ));
+                    continue;
+                }
+                Keyword::Slow(fraction, seconds) => (StatusKind::Slowed, fraction, seconds),
+                Keyword::Burn(damage_per_second, seconds) => {
+                    (StatusKind::Burning, damage_per_second, seconds)
+                }
+                Keyword::Haste(fraction, seconds) => (StatusKind::Hasted, fraction, seconds),
+                Keyword::Stun(seconds) => (StatusKind::Stunned, 1.0, seconds),
+                Keyword::Invulnerable(seconds) => (StatusKind::Invulnerable, 1.0, seconds),
+                _ => continue,
+            };
+
+            writer_apply_status.send(ApplyStatusEvent {
+                target: keyword_hit.target,
+                kind,
+                magnitude,
+                seconds,
+                source,
+            });
+        }
+    }
+}
+
+/// Turns the ticks of burning into damage, credited to whoever set the fire
+pub fn burn_system(
+    mut reader_status_tick: EventReader<StatusTickEvent>,
+    mut writer_inflict_damage: EventWriter<InflictDamageEvent>,
+    player_query: Query<&Player>,
+) {
+    for status_tick in reader_status_tick.read() {
+        if status_tick.kind != StatusKind::Burning {
+            continue;
+        }
+
+        writer_inflict_damage.send(InflictDamageEvent {
+            target: status_tick.target,
+            attacker: status_tick
+                .source
+                .and_then(|source| player_query.get(source).ok())
+                .map(|player| player.id),
+            damage: status_tick.amount,
+            lifesteal: 0.0,
+        });
+    }
+}
+
+/// Applies damage to the health of living entities and tells the clients
+pub fn inflict_damage_system(
+    mut reader_inflict_damage: EventReader<InflictDamageEvent>,
+    mut server_messenger: ServerMessenger,
+    mut writer_sync_entity: EventWriter<SyncEntityEvent>,
+    mut writer_entity_damaged: EventWriter<EntityDamagedEvent>,
+    mut p_query: Query<
+        (
+            &mut Health,
+            &mut EntityState,
+            &NetworkId,
+            Has<SpawnProtection>,
+        ),
+        Without<Death>,
+    >,
+    status_query: Query<&StatusEffects>,
+) {
+    for inflict_damage in reader_inflict_damage.read() {
+        let Ok((mut health, mut entity_state, network_id, spawn_protected)) =
+            p_query.get_mut(inflict_damage.target)
+        else {
+            continue;
+        };
+
+        let invulnerable = status_query
+            .get(inflict_damage.target)
+            .is_ok_and(|status_effects| status_effects.has(StatusKind::Invulnerable));
+
+        // an earlier hit in the same frame may already have killed the entity
+        if spawn_protected || invulnerable || health.current <= 0.0 {
+            continue;
+        }
+
+        let damage = health.current.min(inflict_damage.damage);
+        health.current -= damage;
+
+        let lethal = health.current <= 0.0;
+        if lethal {
             health.current = 0.0;
             *entity_state = EntityState::Dead;
-            if let Some(mut entity_command) = command.get_entity(event.detected) {
-                entity_command.insert(Death::default());
-            }
         } else {
             println!("Entity hit!");
             *entity_state = EntityState::Hit;
         }
 
-        server.broadcast_message(
-            ServerChannel::ServerMessages,
-            bincode::serialize(&ServerMessages::DamageEntity(DamageEntityEvent {
-                entity: event.detected,
-                damage: **dmg,
-            }))
-            .expect("Could not serialize damage entity message."),
-        );
+        writer_entity_damaged.send(EntityDamagedEvent {
+            target: inflict_damage.target,
+            attacker: inflict_damage.attacker,
+            damage,
+            lethal,
+            lifesteal: inflict_damage.lifesteal,
+        });
+        server_messenger.broadcast(ServerMessages::DamageEntity(DamageEntityEvent {
+            network_id: *network_id,
+            damage,
+        }));
         writer_sync_entity.send(SyncEntityEvent {
-            entity: event.detected,
+            entity: inflict_damage.target,
         });
     }
 }
 
+/// Heals attackers by their lifesteal share of the damage they actually dealt
+pub fn lifesteal_system(
+    mut reader_entity_damaged: EventReader<EntityDamagedEvent>,
+    mut writer_restore_health: EventWriter<RestoreHealthEvent>,
+    lobby: Res<ServerLobby>,
+) {
+    for entity_damaged in reader_entity_damaged.read() {
+        if entity_damaged.lifesteal <= 0.0 {
+            continue;
+        }
+
+        let Some(&attacker) = entity_damaged
+            .attacker
+            .and_then(|attacker| lobby.players.get(&attacker.raw()))
+        else {
+            continue;
+        };
+
+        writer_restore_health.send(RestoreHealthEvent {
+            target: attacker,
+            amount: entity_damaged.damage * entity_damaged.lifesteal,
+        });
+    }
+}
+
+/// Marks the entities killed by damage as dead
+pub fn kill_entity_system(
+    mut reader_entity_damaged: EventReader<EntityDamagedEvent>,
+    mut command: Commands,
+) {
+    for entity_damaged in reader_entity_damaged.read() {
+        if !entity_damaged.lethal {
+            continue;
+        }
+
+        if let Some(mut entity_command) = command.get_entity(entity_damaged.target) {
+            entity_command.insert(Death::default());
+        }
+    }
+}
+
+/// Heals living entities up to their maximum health and tells the clients
+pub fn restore_health_system(
+    mut reader_restore_health: EventReader<RestoreHealthEvent>,
+    mut server_messenger: ServerMessenger,
+    mut writer_sync_entity: EventWriter<SyncEntityEvent>,
+    mut query: Query<(&mut Health, &NetworkId), Without<Death>>,
+) {
+    for restore_health in reader_restore_health.read() {
+        let Ok((mut health, network_id)) = query.get_mut(restore_health.target) else {
+            continue;
+        };
+
+        // the dead are not brought back by a heal in the same frame
+        let amount = restore_health.amount.min(health.max - health.current);
+        if health.current <= 0.0 || amount <= 0.0 {
+            continue;
+        }
+
+        health.current += amount;
+
+        server_messenger.broadcast(ServerMessages::KeywordOutcome(KeywordOutcomeEvent::Heal {
+            network_id: *network_id,
+            amount,
+        }));
+        writer_sync_entity.send(SyncEntityEvent {
+            entity: restore_health.target,
+        });
+    }
+}
+
+pub fn projectile_range_system(
+    dt: Res<Time>,
+    mut writer_despawn_projectile: EventWriter<DespawnProjectileEvent>,
+    mut query: Query<(&NetworkId, &Velocity, &mut Range)>,
+) {
+    for (network_id, velocity, mut range) in &mut query {
+        if range.exceeded() {
+            continue;
+        }
+
+        range.travelled += velocity.vector.length() * dt.delta_seconds();
+
+        if range.exceeded() {
+            writer_despawn_projectile.send(DespawnProjectileEvent {
+                network_id: *network_id,
+            });
+        }
+    }
+}
+
+pub fn despawn_projectile(
+    mut reader_despawn_projectile: EventReader<DespawnProjectileEvent>,
+    mut server_messenger: ServerMessenger,
+    mut network_registry: ResMut<NetworkRegistry>,
+    mut command: Commands,
+) {
+    for despawn_projectile in reader_despawn_projectile.read() {
+        // already despawned by an earlier event
+        let Some(entity) = network_registry.remove(&despawn_projectile.network_id) else {
+            continue;
+        };
+
+        if let Some(mut entity_command) = command.get_entity(entity) {
+            entity_command.despawn();
+        }
+
+        server_messenger.broadcast(ServerMessages::DespawnProjectile(*despawn_projectile));
+    }
+}
+
 pub fn on_damage_entity(
     mut events: EventReader<DamageEntityEvent>,
     mut query: Query<&mut Health>,
-    network_mapping: Res<NetworkEntities>,
+    network_registry: Res<NetworkRegistry>,
 ) {
     for event in events.read() {
-        if let Some(entity) = network_mapping.0.get(&event.entity) {
-            if let Ok(mut health) = query.get_mut(*entity) {
+        if let Some(entity) = network_registry.entity(&event.network_id) {
+            if let Ok(mut health) = query.get_mut(entity) {
                 health.current -= event.damage;
             }
         }
@@ -93,22 +513,28 @@ pub fn on_damage_entity(
 
 pub fn spawn_projectile(
     mut reader_spawn_projectile: EventReader<SpawnProjectileEvent>,
+    mut network_registry: ResMut<NetworkRegistry>,
     mut command: Commands,
-    asset_handler: Res<AssetHandler>,
+    sprite_assets: SpriteAssets,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
 ) {
     for spawn_projectile in reader_spawn_projectile.read() {
-        let (texture, animations, hitbox_config) = asset_handler
-            .textures
-            .get(&spawn_projectile.projectile_type.into())
-            .expect("Could not find projectile texture in asset handler.");
+        // ids come from the network, an unknown one skips the projectile
+        let (texture, animations, hitbox_config) =
+            match sprite_assets.get_by_id(spawn_projectile.projectile_type) {
+                Ok(sprite) => sprite,
+                Err(error) => {
+                    println!("Could not spawn projectile: {}.", error);
+                    continue;
+                }
+            };
         let velocity: Velocity = spawn_projectile.velocity.into();
         let mut transform = Transform::from_translation(spawn_projectile.translation.into());
         transform.rotation = Quat::from_rotation_z(velocity.rotation);
 
         let hitbox_config = hitbox_config.expect("Could not find hitbox config for bullet.");
 
-        let mut projectile = ProjectileBundle::new(
+        let projectile = ProjectileBundle::new(
             Animator::import(animations),
             texture_atlases.add(texture.clone()),
             transform,
@@ -120,8 +546,87 @@ pub fn spawn_projectile(
             },
         );
 
-        projectile.damage = Damage(10.0);
+        let projectile_entity = command
+            .spawn((projectile, spawn_projectile.network_id))
+            .id();
+        network_registry.insert(spawn_projectile.network_id, projectile_entity);
+    }
+}
+
+pub fn on_despawn_projectile(
+    mut reader_despawn_projectile: EventReader<DespawnProjectileEvent>,
+    mut network_registry: ResMut<NetworkRegistry>,
+    mut command: Commands,
+) {
+    for despawn_projectile in reader_despawn_projectile.read() {
+        if let Some(entity) = network_registry.remove(&despawn_projectile.network_id) {
+            if let Some(mut entity_command) = command.get_entity(entity) {
+                entity_command.despawn();
+            }
+        }
+    }
+}
+
+/// Lands the keywords of played cards on their targets
+pub fn keyword_card_system(
+    mut reader_card_played: EventReader<CardPlayedEvent>,
+    mut writer_keyword_hit: EventWriter<KeywordHitEvent>,
+    query: Query<(&Transform, Option<&Team>)>,
+) {
+    for card_played in reader_card_played.read() {
+        let CardEffect::Keywords(keywords) = &card_played.effect else {
+            continue;
+        };
+        let Ok((transform, team)) = query.get(card_played.player) else {
+            continue;
+        };
+
+        let from = transform.translation.truncate();
+        let at = query
+            .get(card_played.target)
+            .map_or(from, |(target_transform, _)| {
+                target_transform.translation.truncate()
+            });
+
+        writer_keyword_hit.send(KeywordHitEvent {
+            target: card_played.target,
+            attacker: Some(card_played.owner),
+            at,
+            direction: (at - from).normalize_or_zero(),
+            mask: team.map_or(0, Team::enemy_mask),
+            keywords: Keywords::new(keywords.clone()),
+        });
+    }
+}
 
-        command.spawn(projectile);
+/// Applies the outcomes of keywords resolved on the server
+pub fn on_keyword_outcome(
+    mut reader_keyword_outcome: EventReader<KeywordOutcomeEvent>,
+    network_registry: Res<NetworkRegistry>,
+    mut query: Query<(&mut Health, &mut Transform)>,
+) {
+    for keyword_outcome in reader_keyword_outcome.read() {
+        match *keyword_outcome {
+            KeywordOutcomeEvent::Heal { network_id, amount } => {
+                if let Some(Ok((mut health, _))) = network_registry
+                    .entity(&network_id)
+                    .map(|entity| query.get_mut(entity))
+                {
+                    health.current = (health.current + amount).min(health.max);
+                }
+            }
+            KeywordOutcomeEvent::Knockback {
+                network_id,
+                translation,
+            } => {
+                if let Some(Ok((_, mut transform))) = network_registry
+                    .entity(&network_id)
+                    .map(|entity| query.get_mut(entity))
+                {
+                    transform.translation = translation.into();
+                }
+            }
+            KeywordOutcomeEvent::Explode { .. } => {}
+        }
     }
 }
@@ -1,14 +1,62 @@
 use bevy::prelude::*;
 
-use self::{card::CardPlugin, keyword::KeywordPlugin};
+use crate::{
+    client::sets::Connected,
+    enums::GameState,
+    networking::{is_client, is_server, sets::SimulationSet},
+};
+
+use self::{
+    card::CardPlugin,
+    events::{CardPlayedEvent, HandEvent, PlayCardEvent},
+    keyword::KeywordPlugin,
+    resources::ClientHand,
+    systems::{deal_cards_system, draw_cards_system, on_hand, play_card_system, sync_hand_system},
+};
 
 pub mod card;
+pub mod components;
+pub mod events;
 pub mod keyword;
+pub mod resources;
+mod systems;
 
 pub struct DeckPlugin;
 
 impl Plugin for DeckPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((CardPlugin, KeywordPlugin));
+
+        app.add_systems(
+            Update,
+            (deal_cards_system, draw_cards_system, sync_hand_system)
+                .chain()
+                .run_if(is_server())
+                .run_if(in_state(GameState::Gameloop)),
+        );
+
+        // cards are played on the tick their command is carried out,
+        // the systems resolving their effects run after it
+        app.add_systems(
+            FixedUpdate,
+            play_card_system
+                .in_set(SimulationSet::Gameplay)
+                .run_if(is_server())
+                .run_if(in_state(GameState::Gameloop)),
+        );
+
+        app.add_systems(
+            Update,
+            on_hand
+                .run_if(is_client())
+                .run_if(in_state(GameState::Gameloop))
+                .in_set(Connected),
+        );
+
+        app.add_event::<PlayCardEvent>();
+        app.add_event::<CardPlayedEvent>();
+        app.add_event::<HandEvent>();
+
+        app.insert_resource(ClientHand::default());
     }
 }
@@ -0,0 +1,11 @@
+use bevy::prelude::Resource;
+
+use crate::asset::registry::CardName;
+
+/// The client player's hand, as last sent by the server
+#[derive(Resource, Debug, Default)]
+pub struct ClientHand {
+    pub cards: Vec<CardName>,
+    pub deck: u16,
+    pub discard_pile: u16,
+}
@@ -0,0 +1,193 @@
+use bevy::{
+    ecs::{
+        entity::Entity,
+        event::{EventReader, EventWriter},
+        query::{Changed, Without},
+        system::{Commands, Query, Res, ResMut},
+    },
+    time::Time,
+    transform::components::Transform,
+};
+
+use crate::{
+    asset::resources::{AssetRegistry, AssetsConfig},
+    networking::{networking::ServerMessages, resources::NetworkRegistry},
+    player::components::{Death, Player, Team},
+    server::params::ServerMessenger,
+};
+
+use super::{
+    components::{Deck, DiscardPile, Hand},
+    events::{CardPlayedEvent, HandEvent, PlayCardEvent},
+    resources::ClientHand,
+};
+
+/// Deals every new player a shuffled deck and a full hand
+pub fn deal_cards_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_config: Res<AssetsConfig>,
+    query: Query<(Entity, &Player), Without<Deck>>,
+) {
+    let config = &asset_config.deck;
+
+    for (entity, player) in &query {
+        let shuffle_seed = player.id.raw() ^ time.elapsed().as_nanos() as u64;
+        let mut deck = Deck::new(config.cards.clone(), config.draw_interval, shuffle_seed);
+        let mut discard_pile = DiscardPile::default();
+
+        let cards = (0..config.hand_size)
+            .map_while(|_| deck.draw(&mut discard_pile))
+            .collect();
+
+        commands
+            .entity(entity)
+            .insert((deck, Hand { cards }, discard_pile));
+    }
+}
+
+/// Draws a card every interval while a living player's hand isn't full
+pub fn draw_cards_system(
+    time: Res<Time>,
+    asset_config: Res<AssetsConfig>,
+    mut query: Query<(&mut Deck, &mut Hand, &mut DiscardPile), Without<Death>>,
+) {
+    for (mut deck, mut hand, mut discard_pile) in &mut query {
+        if hand.cards.len() >= asset_config.deck.hand_size {
+            deck.draw_timer.reset();
+            continue;
+        }
+
+        if !deck.draw_timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        if let Some(card) = deck.draw(&mut discard_pile) {
+            hand.cards.push(card);
+        }
+    }
+}
+
+/// Plays the cards players asked for when their target is valid,
+/// the played card goes to the discard pile
+pub fn play_card_system(
+    mut reader_play_card: EventReader<PlayCardEvent>,
+    mut writer_card_played: EventWriter<CardPlayedEvent>,
+    mut server_messenger: ServerMessenger,
+    network_registry: Res<NetworkRegistry>,
+    asset_config: Res<AssetsConfig>,
+    mut player_query: Query<(&mut Hand, &mut DiscardPile, &Team, &Transform), Without<Death>>,
+) {
+    for play_card in reader_play_card.read() {
+        let PlayCardEvent {
+            player: entity,
+            owner,
+            slot,
+            target,
+        } = *play_card;
+
+        // cards without a target are played on the player themselves
+        let target = match target {
+            Some(network_id) => network_registry.entity(&network_id),
+            None => Some(entity),
+        };
+
+        let Ok((hand, _, team, transform)) = player_query.get(entity) else {
+            continue;
+        };
+        if !team.is_playing() {
+            continue;
+        }
+        let Some(card) = hand
+            .cards
+            .get(slot as usize)
+            .and_then(|card| asset_config.cards.get(card))
+        else {
+            continue;
+        };
+
+        let target_error = match target.map(|target| (target, player_query.get(target))) {
+            Some((target, Ok((_, _, target_team, target_transform)))) => {
+                let distance = transform
+                    .translation
+                    .truncate()
+                    .distance(target_transform.translation.truncate());
+
+                if !card.target.allows(*team, *target_team, target == entity) {
+                    Some(format!("{} can't be played on that player", card.name))
+                } else if card.range > 0.0 && distance > card.range {
+                    Some(format!("The target is out of range of {}", card.name))
+                } else {
+                    None
+                }
+            }
+            _ => Some("The target is gone".to_string()),
+        };
+        if let Some(target_error) = target_error {
+            server_messenger.send(owner, ServerMessages::Warning(target_error));
+            continue;
+        }
+
+        let Ok((mut hand, mut discard_pile, _, _)) = player_query.get_mut(entity) else {
+            continue;
+        };
+        let Some(played) = hand.play(slot as usize) else {
+            continue;
+        };
+        discard_pile.cards.push(played.clone());
+
+        writer_card_played.send(CardPlayedEvent {
+            player: entity,
+            owner,
+            target: target.unwrap_or(entity),
+            card: played,
+            effect: card.effect.clone(),
+        });
+    }
+}
+
+/// Sends players their hand whenever it changes, only to them
+pub fn sync_hand_system(
+    mut server_messenger: ServerMessenger,
+    asset_registry: Res<AssetRegistry>,
+    query: Query<(&Player, &Hand, &Deck, &DiscardPile), Changed<Hand>>,
+) {
+    for (player, hand, deck, discard_pile) in &query {
+        let cards = hand
+            .cards
+            .iter()
+            .filter_map(|card| asset_registry.cards.id(card).ok())
+            .collect();
+
+        server_messenger.send(
+            player.id,
+            ServerMessages::Hand(HandEvent {
+                cards,
+                deck: deck.cards.len() as u16,
+                discard_pile: discard_pile.cards.len() as u16,
+            }),
+        );
+    }
+}
+
+pub fn on_hand(
+    mut reader_hand: EventReader<HandEvent>,
+    asset_registry: Res<AssetRegistry>,
+    mut client_hand: ResMut<ClientHand>,
+) {
+    for hand in reader_hand.read() {
+        client_hand.cards = hand
+            .cards
+            .iter()
+            .filter_map(|&id| match asset_registry.cards.name(id) {
+                Ok(card) => Some(card.clone()),
+                Err(error) => {
+                    println!("Could not show card: {}.", error);
+                    None
+                }
+            })
+            .collect();
+        client_hand.deck = hand.deck;
+        client_hand.discard_pile = hand.discard_pile;
+    }
+}
@@ -1,12 +1,13 @@
 use bevy::ecs::component::Component;
 use serde::{Deserialize, Serialize};
 
-#[derive(Component, Serialize, Deserialize, Default, Clone, Copy)]
+#[derive(Component, Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CollisionGroups {
     Player = 1,
     Enemy = 2,
     Teammate = 4,
     Projectile = 8,
+    /// The first team's layer, the teams after it take the bits that follow
     #[default]
     TeamAlpha = 16,
     TeamBravo = 32,
@@ -31,13 +32,3 @@ impl From<u32> for CollisionGroups {
         }
     }
 }
-
-impl CollisionGroups {
-    pub fn enemy_teams(&self) -> u32 {
-        match self {
-            CollisionGroups::TeamAlpha => CollisionGroups::TeamBravo as u32,
-            CollisionGroups::TeamBravo => CollisionGroups::TeamAlpha as u32,
-            _ => 0,
-        }
-    }
-}
@@ -32,6 +32,7 @@ pub enum GameState {
     Loading,
     Connecting,
     Gameloop,
+    Disconnected,
 }
 
 /**
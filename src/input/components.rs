@@ -1,5 +1,11 @@
+use std::collections::VecDeque;
+
 use bevy::{ecs::component::Component, math::Vec2, prelude::Deref};
 
+use crate::player::events::PlayerCommand;
+
+use super::resources::PlayerInput;
+
 /**
  * Controllable
  *
@@ -23,3 +29,140 @@ pub struct PlayerCamera;
  */
 #[derive(Component, Deref, Default)]
 pub struct Aim(pub Vec2);
+
+/**
+ * Input Queue
+ *
+ * The inputs a client sent that the server has yet to simulate,
+ * oldest first, exactly one is simulated every tick
+ */
+#[derive(Component, Debug, Default)]
+pub struct InputQueue {
+    inputs: VecDeque<PlayerInput>,
+    /// The newest sequence queued, inputs up to it are never queued again
+    last_sequence: u32,
+}
+
+impl InputQueue {
+    /// The most inputs waiting, a client running ahead loses its oldest ones
+    pub const CAPACITY: usize = 16;
+
+    /// Whether the input is newer than every input queued so far
+    pub fn is_new(&self, sequence: u32) -> bool {
+        sequence > self.last_sequence
+    }
+
+    /// Queues the input unless it was queued before
+    pub fn push(&mut self, player_input: PlayerInput) {
+        if !self.is_new(player_input.sequence) {
+            return;
+        }
+
+        if self.inputs.len() == Self::CAPACITY {
+            self.inputs.pop_front();
+        }
+
+        self.last_sequence = player_input.sequence;
+        self.inputs.push_back(player_input);
+    }
+
+    /// The next input to simulate
+    pub fn pop(&mut self) -> Option<PlayerInput> {
+        self.inputs.pop_front()
+    }
+}
+
+/**
+ * Command Queue
+ *
+ * The commands a client sent that the server has yet to simulate,
+ * received between ticks and carried out on the next one
+ */
+#[derive(Component, Debug, Default)]
+pub struct CommandQueue {
+    commands: VecDeque<PlayerCommand>,
+}
+
+impl CommandQueue {
+    /// The most commands waiting, the oldest are dropped past it
+    pub const CAPACITY: usize = 16;
+
+    pub fn push(&mut self, player_command: PlayerCommand) {
+        if self.commands.len() == Self::CAPACITY {
+            self.commands.pop_front();
+        }
+
+        self.commands.push_back(player_command);
+    }
+
+    /// The commands to carry out this tick, oldest first
+    pub fn drain(&mut self) -> impl Iterator<Item = PlayerCommand> + '_ {
+        self.commands.drain(..)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(sequence: u32) -> PlayerInput {
+        PlayerInput {
+            sequence,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn input_queues_keep_every_new_input_once_in_order() {
+        let mut input_queue = InputQueue::default();
+
+        // redundant packets repeat the inputs sent before them
+        for packet in [[1, 2, 3], [2, 3, 4], [4, 5, 6]] {
+            for sequence in packet {
+                input_queue.push(input(sequence));
+            }
+        }
+
+        let sequences: Vec<u32> = std::iter::from_fn(|| input_queue.pop())
+            .map(|player_input| player_input.sequence)
+            .collect();
+        assert_eq!(sequences, vec![1, 2, 3, 4, 5, 6]);
+
+        input_queue.push(input(5));
+        assert!(input_queue.pop().is_none());
+    }
+
+    #[test]
+    fn input_queues_drop_the_oldest_inputs_when_full() {
+        let mut input_queue = InputQueue::default();
+        for sequence in 1..=InputQueue::CAPACITY as u32 + 2 {
+            input_queue.push(input(sequence));
+        }
+
+        assert_eq!(
+            input_queue.pop().map(|player_input| player_input.sequence),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn command_queues_drain_every_command_once_in_order() {
+        let mut command_queue = CommandQueue::default();
+        for slot in 0..CommandQueue::CAPACITY as u8 + 2 {
+            command_queue.push(PlayerCommand::ChangeEquipment { slot });
+        }
+
+        let slots: Vec<u8> = command_queue
+            .drain()
+            .filter_map(|player_command| match player_command {
+                PlayerCommand::ChangeEquipment { slot } => Some(slot),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            slots,
+            (2..CommandQueue::CAPACITY as u8 + 2).collect::<Vec<_>>()
+        );
+        assert!(command_queue.drain().next().is_none());
+    }
+}
@@ -1,11 +1,11 @@
 use bevy::{
-    app::{App, Plugin, Update},
+    app::{App, FixedUpdate, Plugin, Update},
     ecs::schedule::{common_conditions::in_state, IntoSystemConfigs},
 };
 
 use crate::{
     enums::GameState,
-    networking::{is_client, is_server},
+    networking::{is_client, is_server, sets::SimulationSet},
 };
 
 use self::systems::*;
@@ -18,24 +18,50 @@ pub struct InputPlugin;
 
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
+        // the client samples and predicts one input per simulation tick
         app.add_systems(
-            Update,
+            FixedUpdate,
             (
                 capture_player_input_system,
-                capture_player_command_input_system,
+                handle_input,
                 client_send_player_input_system,
+            )
+                .chain()
+                .in_set(SimulationSet::Input)
+                .run_if(in_state(GameState::Gameloop))
+                .run_if(is_client()),
+        );
+
+        app.add_systems(
+            Update,
+            (
+                capture_player_command_input_system,
+                capture_card_input_system,
                 client_send_player_command_events,
-                handle_input,
             )
                 .run_if(in_state(GameState::Gameloop))
                 .run_if(is_client()),
         );
 
+        // the server simulates one queued input per player every tick,
+        // and carries out the commands queued since the last one
+        app.add_systems(
+            FixedUpdate,
+            (
+                dequeue_player_input_system,
+                handle_input,
+                dequeue_player_command_system,
+            )
+                .chain()
+                .in_set(SimulationSet::Input)
+                .run_if(in_state(GameState::Gameloop))
+                .run_if(is_server()),
+        );
+
         app.add_systems(
             Update,
             (
                 server_receive_player_input_system,
-                handle_input,
                 server_receive_player_command_system,
             )
                 .run_if(in_state(GameState::Gameloop))
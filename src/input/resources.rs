@@ -3,9 +3,33 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, Component, Resource)]
 pub struct PlayerInput {
+    pub sequence: u32,
     pub up: bool,
     pub down: bool,
     pub left: bool,
     pub right: bool,
     pub aim: Vec2,
 }
+
+impl PlayerInput {
+    /// The normalized direction of movement the input describes
+    pub fn movement(&self) -> Vec2 {
+        let mut fx = 0.0;
+        let mut fy = 0.0;
+
+        if self.left {
+            fx -= 1.0;
+        }
+        if self.right {
+            fx += 1.0;
+        }
+        if self.up {
+            fy += 1.0;
+        }
+        if self.down {
+            fy -= 1.0;
+        }
+
+        Vec2::new(fx, fy).normalize_or_zero()
+    }
+}
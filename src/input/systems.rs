@@ -1,26 +1,42 @@
+use std::time::Duration;
+
+use bevy::input::mouse::MouseWheel;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use bevy_renet::renet::RenetClient;
 
 use crate::{
     animation::events::PlayAnimationEvent,
-    client::resources::{ClientId, ClientLobby, CurrentClientId},
-    deck::card::equipment::{components::Equipped, events::EquippedUse},
+    asset::resources::{AssetsConfig, CardTarget},
+    client::resources::{ClientId, ClientLobby, CurrentClientId, SentInputs, ServerClock},
+    deck::{
+        card::equipment::{
+            components::Inventory,
+            events::{EquippedChange, EquippedReload, EquippedUse},
+        },
+        events::PlayCardEvent,
+        resources::ClientHand,
+    },
     enums::EntityState,
     input::resources::PlayerInput,
-    networking::channels::ClientChannel,
+    networking::resources::TickRate,
+    networking::{channels::ClientChannel, codec, components::NetworkId, models::PackedInput},
     physics::components::Velocity,
     player::{
-        components::{Death, Player},
+        components::{Death, Player, Team},
         events::PlayerCommand,
     },
     server::{
-        events::{ClientSentCommandEvent, ClientSentInputEvent},
-        resources::ServerLobby,
+        events::{ClientSentCommandEvent, ClientSentInputEvent, ClientViolationEvent},
+        resources::{ServerLobby, Violation, ViolationPolicy},
     },
+    stats::components::{StatusEffects, StatusKind},
 };
 
-use super::components::{Aim, Controllable, PlayerCamera};
+use super::components::{Aim, CommandQueue, Controllable, InputQueue, PlayerCamera};
+
+/// How close to a player the cursor has to be to play a card on them
+const CARD_TARGET_RADIUS: f32 = 32.0;
 
 pub fn capture_player_input_system(
     mut player_input: ResMut<PlayerInput>,
@@ -51,6 +67,7 @@ pub fn capture_player_input_system(
     player_input.down = keyboard_input.pressed(KeyCode::S);
     player_input.left = keyboard_input.pressed(KeyCode::A);
     player_input.right = keyboard_input.pressed(KeyCode::D);
+    player_input.sequence += 1;
 
     if let Some(current_player_info) = lobby.players.get(&ClientId(client_id.0)) {
         command
@@ -62,79 +79,301 @@ pub fn capture_player_input_system(
 
 pub fn client_send_player_input_system(
     player_input: Res<PlayerInput>,
+    mut sent_inputs: ResMut<SentInputs>,
     mut client: ResMut<RenetClient>,
 ) {
-    // This is not optimized. We should only send the inputs that are true. (Should be serailized as inputs: Vec<Input>, aim: [f32; 2])
-    let input_message = bincode::serialize(&*player_input).unwrap();
+    let input_packet = sent_inputs.packet(PackedInput::from(&*player_input));
+    let input_message = codec::encode(&input_packet);
 
     client.send_message(ClientChannel::Input, input_message);
 }
 
 pub fn capture_player_command_input_system(
     mouse_input: Res<Input<MouseButton>>,
+    mut reader_mouse_wheel: EventReader<MouseWheel>,
     player_input: Res<PlayerInput>,
-    _keyboard_input: Res<Input<KeyCode>>,
-    player_query: Query<&Children, (With<Controllable>, With<Player>)>,
-    equipment_query: Query<Entity, With<Equipped>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    player_query: Query<&Inventory, (With<Controllable>, With<Player>)>,
     mut writer_player_command_event: EventWriter<PlayerCommand>,
+    server_clock: Res<ServerClock>,
 ) {
-    if mouse_input.pressed(MouseButton::Left) {
-        if let Ok(children) = player_query.get_single() {
-            for &child in children.iter() {
-                if let Ok(_equipment_entity) = equipment_query.get(child) {
-                    writer_player_command_event.send(PlayerCommand::UseEquipment {
-                        cast_at: player_input.aim,
-                    })
-                }
-            }
+    let inventory = player_query.get_single().ok();
+
+    if mouse_input.pressed(MouseButton::Left)
+        && inventory.is_some_and(|inventory| inventory.active_equipment().is_some())
+    {
+        writer_player_command_event.send(PlayerCommand::UseEquipment {
+            cast_at: player_input.aim,
+            render_tick: server_clock.render_tick.max(0.0) as u64,
+        })
+    }
+
+    if keyboard_input.just_pressed(KeyCode::R) {
+        writer_player_command_event.send(PlayerCommand::Reload);
+    }
+
+    if let Some(inventory) = inventory {
+        // 1 to 9 put the numbered inventory slots in hand
+        let slot_keys = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+            KeyCode::Key8,
+            KeyCode::Key9,
+        ];
+        let mut slot = slot_keys
+            .into_iter()
+            .position(|slot_key| keyboard_input.just_pressed(slot_key));
+
+        // the mouse wheel cycles through the slots
+        let scrolled: f32 = reader_mouse_wheel.read().map(|wheel| wheel.y).sum();
+        if slot.is_none() && scrolled != 0.0 {
+            slot = Some(inventory.cycle(if scrolled > 0.0 { -1 } else { 1 }));
+        }
+
+        if let Some(slot) = slot.filter(|slot| inventory.can_switch(*slot)) {
+            writer_player_command_event.send(PlayerCommand::ChangeEquipment { slot: slot as u8 });
+        }
+    } else {
+        reader_mouse_wheel.clear();
+    }
+
+    // F10 spectates, F1 to F8 join the numbered teams
+    if keyboard_input.just_pressed(KeyCode::F10) {
+        writer_player_command_event.send(PlayerCommand::JoinTeam {
+            team: Team::Spectator,
+        });
+    }
+    let team_keys = [
+        KeyCode::F1,
+        KeyCode::F2,
+        KeyCode::F3,
+        KeyCode::F4,
+        KeyCode::F5,
+        KeyCode::F6,
+        KeyCode::F7,
+        KeyCode::F8,
+    ];
+    for (index, team_key) in team_keys.into_iter().enumerate() {
+        if keyboard_input.just_pressed(team_key) {
+            writer_player_command_event.send(PlayerCommand::JoinTeam {
+                team: Team::Index(index as u8),
+            });
         }
     }
 }
 
-// TODO: Probably needs some sort of throttilng to prevent spamming
+pub fn capture_card_input_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    player_input: Res<PlayerInput>,
+    client_hand: Res<ClientHand>,
+    asset_config: Res<AssetsConfig>,
+    mut writer_player_command_event: EventWriter<PlayerCommand>,
+    target_query: Query<(&NetworkId, &Transform, Has<Death>), With<Player>>,
+) {
+    // Z, X, C, V and B play the cards in the hand slots
+    let card_keys = [KeyCode::Z, KeyCode::X, KeyCode::C, KeyCode::V, KeyCode::B];
+    let Some(slot) = card_keys
+        .into_iter()
+        .position(|card_key| keyboard_input.just_pressed(card_key))
+        .filter(|slot| *slot < client_hand.cards.len())
+    else {
+        return;
+    };
+
+    let own_card = asset_config
+        .cards
+        .get(&client_hand.cards[slot])
+        .is_none_or(|card| card.target == CardTarget::Own);
+
+    // the card is played on the player under the cursor, or on the player themselves
+    let target = target_query
+        .iter()
+        .filter(|_| !own_card)
+        .filter(|(_, _, dead)| !dead)
+        .map(|(network_id, transform, _)| {
+            let distance = transform.translation.truncate().distance(player_input.aim);
+            (network_id, distance)
+        })
+        .filter(|(_, distance)| *distance <= CARD_TARGET_RADIUS)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(network_id, _)| *network_id);
+
+    writer_player_command_event.send(PlayerCommand::PlayCard {
+        slot: slot as u8,
+        target,
+    });
+}
+
 pub fn client_send_player_command_events(
     mut client: ResMut<RenetClient>,
     mut reader_player_command_event: EventReader<PlayerCommand>,
+    mut last_use: Local<Option<Duration>>,
+    tick_rate: Res<TickRate>,
+    time: Res<Time>,
 ) {
+    let now = time.elapsed();
+
     for player_command_event in reader_player_command_event.read() {
-        let player_command_message = bincode::serialize(&player_command_event).unwrap();
+        // holding the trigger asks every frame, the server only needs it once a tick
+        if let PlayerCommand::UseEquipment { .. } = player_command_event {
+            if last_use.is_some_and(|last_use| {
+                now.saturating_sub(last_use).as_secs_f64() < 1.0 / **tick_rate
+            }) {
+                continue;
+            }
+            *last_use = Some(now);
+        }
+
+        let player_command_message = codec::encode(&player_command_event);
         client.send_message(ClientChannel::Command, player_command_message);
     }
 }
 
 // TODO: Rename possibly on_player_input
 pub fn server_receive_player_input_system(
-    mut command: Commands,
     mut reader_player_input_event: EventReader<ClientSentInputEvent>,
+    mut writer_client_violation: EventWriter<ClientViolationEvent>,
     lobby: ResMut<ServerLobby>,
+    violation_policy: Res<ViolationPolicy>,
+    mut player_query: Query<(&Transform, &mut InputQueue), With<Player>>,
 ) {
     for player_input_event in reader_player_input_event.read() {
         let player_input = player_input_event.0;
         let client_id = player_input_event.1;
 
-        if let Some(player_entity) = lobby.players.get(&client_id) {
-            command.entity(*player_entity).insert(player_input);
+        let Some(Ok((transform, mut input_queue))) = lobby
+            .players
+            .get(&client_id)
+            .map(|player_entity| player_query.get_mut(*player_entity))
+        else {
+            continue;
+        };
+
+        // every packet repeats the inputs before it, only new ones are queued
+        if !input_queue.is_new(player_input.sequence) {
+            continue;
+        }
+
+        if !violation_policy.is_valid_aim(player_input.aim, Some(transform.translation.truncate()))
+        {
+            writer_client_violation.send(ClientViolationEvent(Violation::InvalidAim, client_id));
+            continue;
+        }
+
+        input_queue.push(player_input);
+    }
+}
+
+/// Takes the next queued input of every player to simulate this tick,
+/// players without one keep their previous input
+pub fn dequeue_player_input_system(
+    mut command: Commands,
+    mut query: Query<(Entity, &mut InputQueue, Option<&mut PlayerInput>)>,
+) {
+    for (entity, mut input_queue, current_input) in &mut query {
+        let Some(player_input) = input_queue.pop() else {
+            continue;
+        };
+
+        match current_input {
+            Some(mut current_input) => *current_input = player_input,
+            None => {
+                command.entity(entity).insert(player_input);
+            }
         }
     }
 }
 
 // TODO: Rename possibly on_player_command
+/// Queues the commands players sent, they are carried out on the next tick
 pub fn server_receive_player_command_system(
-    mut writer_equippable_use: EventWriter<EquippedUse>,
     mut reader_player_command_event: EventReader<ClientSentCommandEvent>,
     lobby: ResMut<ServerLobby>,
+    mut player_query: Query<&mut CommandQueue, With<Player>>,
 ) {
     for player_command_event in reader_player_command_event.read() {
         let player_command = &player_command_event.0;
         let client_id = player_command_event.1;
-        if let Some(player_entity) = lobby.players.get(&client_id) {
+
+        // team changes are handled by the team plugin
+        if let PlayerCommand::JoinTeam { .. } = player_command {
+            continue;
+        }
+
+        if let Some(Ok(mut command_queue)) = lobby
+            .players
+            .get(&client_id)
+            .map(|player_entity| player_query.get_mut(*player_entity))
+        {
+            command_queue.push(player_command.clone());
+        }
+    }
+}
+
+/// Carries out the commands players queued since the last tick
+pub fn dequeue_player_command_system(
+    mut writer_equippable_use: EventWriter<EquippedUse>,
+    mut writer_equipped_reload: EventWriter<EquippedReload>,
+    mut writer_equipped_change: EventWriter<EquippedChange>,
+    mut writer_play_card: EventWriter<PlayCardEvent>,
+    mut writer_client_violation: EventWriter<ClientViolationEvent>,
+    violation_policy: Res<ViolationPolicy>,
+    mut query: Query<(
+        Entity,
+        &Player,
+        &Transform,
+        &StatusEffects,
+        &mut CommandQueue,
+    )>,
+) {
+    for (entity, player, transform, status_effects, mut command_queue) in &mut query {
+        let client_id = player.id.raw();
+
+        for player_command in command_queue.drain() {
             match player_command {
-                PlayerCommand::UseEquipment { cast_at } => {
+                PlayerCommand::UseEquipment {
+                    cast_at,
+                    render_tick,
+                } => {
+                    // stunned players can't fire
+                    if status_effects.has(StatusKind::Stunned) {
+                        continue;
+                    }
+
+                    if !violation_policy
+                        .is_valid_aim(cast_at, Some(transform.translation.truncate()))
+                    {
+                        writer_client_violation
+                            .send(ClientViolationEvent(Violation::InvalidAim, client_id));
+                        continue;
+                    }
+
                     writer_equippable_use.send(EquippedUse {
-                        entity: *player_entity,
-                        at: cast_at.clone(),
+                        entity,
+                        at: cast_at,
+                        render_tick,
                     })
                 }
+                PlayerCommand::Reload => writer_equipped_reload.send(EquippedReload { entity }),
+                PlayerCommand::ChangeEquipment { slot } => {
+                    writer_equipped_change.send(EquippedChange {
+                        entity,
+                        slot: slot as usize,
+                    })
+                }
+                PlayerCommand::PlayCard { slot, target } => writer_play_card.send(PlayCardEvent {
+                    player: entity,
+                    owner: player.id,
+                    slot,
+                    target,
+                }),
+                // team changes are never queued
+                PlayerCommand::JoinTeam { .. } => {}
             }
         }
     }
@@ -145,30 +384,18 @@ pub fn handle_input(
     mut query: Query<(&PlayerInput, &mut Velocity, &mut EntityState, Entity), Without<Death>>,
 ) {
     for (player_input, mut vel, mut state, entity) in &mut query {
-        let mut fx = 0.0;
-        let mut fy = 0.0;
-
-        if player_input.left {
-            fx -= 1.0;
-        }
-        if player_input.right {
-            fx += 1.0;
-        }
-        if player_input.up {
-            fy += 1.0;
-        }
-        if player_input.down {
-            fy -= 1.0;
-        }
+        let force = player_input.movement();
 
-        let force = Vec2::new(fx, fy).normalize_or_zero();
-
-        if force != Vec2::ZERO {
-            *state = crate::enums::EntityState::Walk;
-            writer_play_animation.send(PlayAnimationEvent::new(entity, "Walk"));
+        let movement_state = if force != Vec2::ZERO {
+            EntityState::Walk
         } else {
-            *state = crate::enums::EntityState::Idle;
-            writer_play_animation.send(PlayAnimationEvent::new(entity, "Idle"));
+            EntityState::Idle
+        };
+        writer_play_animation.send(PlayAnimationEvent::new(entity, &movement_state.to_string()));
+
+        // reloading holds the state until the equipment finishes
+        if *state != EntityState::Reload {
+            *state = movement_state;
         }
 
         vel.vector.x = force.x * *vel.current_speed;
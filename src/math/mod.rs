@@ -9,3 +9,43 @@ pub fn angle_between(from: &Vec2, to: &Vec2) -> f32 {
 pub fn vec2_from_vec3(from: &Vec3) -> Vec2 {
     Vec2::new(from.x, from.y)
 }
+
+/// Deterministic pseudo random value between -1.0 and 1.0
+///
+/// The same seed always yields the same value (splitmix64), so
+/// anything derived from it can be reproduced on the server and clients
+pub fn seeded_unit(seed: u64) -> f32 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+
+    // use the upper 24 bits to fill the f32 mantissa
+    let unit = (z >> 40) as f32 / (1u64 << 24) as f32;
+    unit * 2.0 - 1.0
+}
+
+/// Deterministic index in `0..len` derived from a seed, `len` must not be 0
+pub fn seeded_index(seed: u64, len: usize) -> usize {
+    let unit = (seeded_unit(seed) + 1.0) / 2.0;
+    ((unit * len as f32) as usize).min(len - 1)
+}
+
+/// 64 bit FNV-1a hash of the bytes
+///
+/// Stable across builds and platforms, unlike the std hasher
+pub const fn hash_bytes(bytes: &[u8]) -> u64 {
+    extend_hash(0xCBF2_9CE4_8422_2325, bytes)
+}
+
+/// Continues an FNV-1a hash with more bytes
+pub const fn extend_hash(mut hash: u64, bytes: &[u8]) -> u64 {
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01B3);
+        i += 1;
+    }
+
+    hash
+}
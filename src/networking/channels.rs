@@ -1,9 +1,21 @@
 use bevy_renet::renet::{ChannelConfig, SendType};
 use std::time::Duration;
 
+/// How many messages a client may send on a channel
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    /// Messages that may be sent at once
+    pub burst: f64,
+    /// Messages allowed per second once the burst is spent
+    pub per_second: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ClientChannel {
     Input,
     Command,
+    SnapshotAck,
+    Handshake,
 }
 
 impl From<ClientChannel> for u8 {
@@ -11,19 +23,48 @@ impl From<ClientChannel> for u8 {
         match channel_id {
             ClientChannel::Command => 0,
             ClientChannel::Input => 1,
+            ClientChannel::SnapshotAck => 2,
+            ClientChannel::Handshake => 3,
         }
     }
 }
 
 impl ClientChannel {
+    /// The largest message accepted on the channel
+    pub fn max_message_bytes(&self) -> usize {
+        match self {
+            ClientChannel::Input => 256,
+            ClientChannel::Command => 256,
+            ClientChannel::SnapshotAck => 64,
+            ClientChannel::Handshake => 64,
+        }
+    }
+
+    /// The rate a client may send messages on the channel at,
+    /// for a server running at `tick_rate`
+    pub fn rate_limit(&self, tick_rate: f64) -> RateLimit {
+        match self {
+            // one per tick, with room for the ticks a slow frame catches up on
+            ClientChannel::Input | ClientChannel::Command | ClientChannel::SnapshotAck => {
+                RateLimit {
+                    burst: tick_rate,
+                    per_second: tick_rate * 1.5,
+                }
+            }
+            ClientChannel::Handshake => RateLimit {
+                burst: 2.0,
+                per_second: 0.1,
+            },
+        }
+    }
+
     pub fn channels_config() -> Vec<ChannelConfig> {
         vec![
+            // lost inputs are covered by the redundancy of the next packet
             ChannelConfig {
                 channel_id: Self::Input.into(),
                 max_memory_usage_bytes: 5 * 1024 * 1024,
-                send_type: SendType::ReliableOrdered {
-                    resend_time: Duration::ZERO,
-                },
+                send_type: SendType::Unreliable,
             },
             ChannelConfig {
                 channel_id: Self::Command.into(),
@@ -32,13 +73,27 @@ impl ClientChannel {
                     resend_time: Duration::ZERO,
                 },
             },
+            ChannelConfig {
+                channel_id: Self::SnapshotAck.into(),
+                max_memory_usage_bytes: 1024 * 1024,
+                send_type: SendType::Unreliable,
+            },
+            ChannelConfig {
+                channel_id: Self::Handshake.into(),
+                max_memory_usage_bytes: 1024 * 1024,
+                send_type: SendType::ReliableOrdered {
+                    resend_time: Duration::from_millis(200),
+                },
+            },
         ]
     }
 }
 
+#[derive(Debug, Clone, Copy)]
 pub enum ServerChannel {
     ServerMessages,
     NetworkedEntities,
+    Handshake,
 }
 
 impl From<ServerChannel> for u8 {
@@ -46,11 +101,22 @@ impl From<ServerChannel> for u8 {
         match channel_id {
             ServerChannel::NetworkedEntities => 0,
             ServerChannel::ServerMessages => 1,
+            ServerChannel::Handshake => 2,
         }
     }
 }
 
 impl ServerChannel {
+    /// The largest message accepted on the channel
+    pub fn max_message_bytes(&self) -> usize {
+        match self {
+            ServerChannel::NetworkedEntities => 64 * 1024,
+            ServerChannel::ServerMessages => 16 * 1024,
+            // the response carries the id tables of the asset registries
+            ServerChannel::Handshake => 16 * 1024,
+        }
+    }
+
     pub fn channels_config() -> Vec<ChannelConfig> {
         vec![
             ChannelConfig {
@@ -65,6 +131,13 @@ impl ServerChannel {
                     resend_time: Duration::from_millis(200),
                 },
             },
+            ChannelConfig {
+                channel_id: Self::Handshake.into(),
+                max_memory_usage_bytes: 1024 * 1024,
+                send_type: SendType::ReliableOrdered {
+                    resend_time: Duration::from_millis(200),
+                },
+            },
         ]
     }
 }
@@ -0,0 +1,302 @@
+use std::fmt;
+
+use bincode::Options;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Version of the wire encoding, prefixed to every message and
+/// bumped whenever the layout of a message changes
+pub const CODEC_VERSION: u8 = 1;
+
+/// Errors raised while decoding a message received over the network
+#[derive(Debug)]
+pub enum DecodeError {
+    Empty,
+    TooLarge { size: usize, limit: usize },
+    Version { expected: u8, found: u8 },
+    Malformed(bincode::Error),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Empty => write!(f, "empty message"),
+            DecodeError::TooLarge { size, limit } => {
+                write!(
+                    f,
+                    "message of {} bytes exceeds the {} byte limit",
+                    size, limit
+                )
+            }
+            DecodeError::Version { expected, found } => {
+                write!(f, "codec version {} expected, found {}", expected, found)
+            }
+            DecodeError::Malformed(error) => write!(f, "malformed message: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Encodes a message for sending, prefixed with the codec version
+pub fn encode<T: Serialize>(message: &T) -> Vec<u8> {
+    let mut bytes = vec![CODEC_VERSION];
+    options()
+        .serialize_into(&mut bytes, message)
+        .expect("Could not serialize message.");
+
+    bytes
+}
+
+/// Decodes a message of at most `limit` bytes
+///
+/// Collection lengths inside the message are bounded by the limit as well,
+/// so a malformed length can't make the decoder allocate past it
+pub fn decode<T: DeserializeOwned>(bytes: &[u8], limit: usize) -> Result<T, DecodeError> {
+    if bytes.len() > limit {
+        return Err(DecodeError::TooLarge {
+            size: bytes.len(),
+            limit,
+        });
+    }
+
+    let (&version, payload) = bytes.split_first().ok_or(DecodeError::Empty)?;
+    if version != CODEC_VERSION {
+        return Err(DecodeError::Version {
+            expected: CODEC_VERSION,
+            found: version,
+        });
+    }
+
+    decode_payload(payload, limit)
+}
+
+/// Encodes a value nested inside another message, without the version prefix
+pub fn encode_payload<T: Serialize>(value: &T) -> Vec<u8> {
+    options()
+        .serialize(value)
+        .expect("Could not serialize message.")
+}
+
+/// Decodes a value nested inside another message, bounded by `limit` bytes
+pub fn decode_payload<T: DeserializeOwned>(bytes: &[u8], limit: usize) -> Result<T, DecodeError> {
+    options()
+        .with_limit(limit as u64)
+        .deserialize(bytes)
+        .map_err(DecodeError::Malformed)
+}
+
+fn options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Vec2;
+    use serde::{de::DeserializeOwned, Serialize};
+
+    use super::*;
+    use crate::{
+        asset::registry::RegistryTable,
+        enums::EntityState,
+        input::resources::PlayerInput,
+        networking::{
+            channels::{ClientChannel, ServerChannel},
+            components::NetworkId,
+            models::{
+                EntityDelta, Handshake, HandshakeResponse, InputPacket, NetworkedEntities,
+                PackedInput, QuantizedEntity, SnapshotAck,
+            },
+            networking::{ServerMessageEnvelope, ServerMessages},
+        },
+        player::events::PlayerCommand,
+    };
+
+    /// Xorshift generator, so every run fuzzes the same bytes
+    struct Fuzzer(u64);
+
+    impl Fuzzer {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn bytes(&mut self, len: usize) -> Vec<u8> {
+            (0..len).map(|_| self.next() as u8).collect()
+        }
+    }
+
+    /// Feeds a valid message and every kind of broken one through `decode`
+    fn fuzz<T: Serialize + DeserializeOwned>(message: &T, limit: usize) {
+        let encoded = encode(message);
+        assert!(decode::<T>(&encoded, limit).is_ok());
+
+        for len in 0..encoded.len() {
+            assert!(decode::<T>(&encoded[..len], limit).is_err());
+        }
+
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert!(decode::<T>(&trailing, limit).is_err());
+
+        let mut wrong_version = encoded.clone();
+        wrong_version[0] = CODEC_VERSION.wrapping_add(1);
+        assert!(matches!(
+            decode::<T>(&wrong_version, limit),
+            Err(DecodeError::Version { .. })
+        ));
+
+        let mut oversized = encoded;
+        oversized.resize(limit + 1, 0xff);
+        assert!(matches!(
+            decode::<T>(&oversized, limit),
+            Err(DecodeError::TooLarge { .. })
+        ));
+
+        // random bytes only decode when they happen to be the exact
+        // encoding of a message, anything else is an error
+        let mut fuzzer = Fuzzer(0x9e37_79b9_7f4a_7c15);
+        for round in 0..512 {
+            let len = fuzzer.next() as usize % (limit + 1);
+            let mut bytes = fuzzer.bytes(len);
+            if round % 2 == 0 {
+                if let Some(version) = bytes.first_mut() {
+                    *version = CODEC_VERSION;
+                }
+            }
+
+            if let Ok(decoded) = decode::<T>(&bytes, limit) {
+                assert_eq!(encode(&decoded), bytes);
+            }
+        }
+    }
+
+    fn player_input(sequence: u32) -> PackedInput {
+        PackedInput::from(&PlayerInput {
+            sequence,
+            up: true,
+            right: true,
+            aim: Vec2::new(12.5, -40.0),
+            ..Default::default()
+        })
+    }
+
+    fn quantized_entity(x: f32) -> QuantizedEntity {
+        QuantizedEntity::new(Vec2::new(x, 3.0).extend(0.0), Vec2::ZERO, EntityState::Idle)
+    }
+
+    #[test]
+    fn input_packets_reject_malformed_bytes() {
+        let packet = InputPacket {
+            inputs: (0..InputPacket::REDUNDANCY as u32)
+                .map(player_input)
+                .collect(),
+        };
+
+        fuzz(&packet, ClientChannel::Input.max_message_bytes());
+    }
+
+    #[test]
+    fn player_commands_reject_malformed_bytes() {
+        let limit = ClientChannel::Command.max_message_bytes();
+
+        fuzz(
+            &PlayerCommand::UseEquipment {
+                cast_at: Vec2::new(1.0, 2.0),
+                render_tick: 42,
+            },
+            limit,
+        );
+        fuzz(
+            &PlayerCommand::PlayCard {
+                slot: 1,
+                target: Some(NetworkId(7)),
+            },
+            limit,
+        );
+    }
+
+    #[test]
+    fn snapshot_acks_reject_malformed_bytes() {
+        fuzz(
+            &SnapshotAck { tick: 1234 },
+            ClientChannel::SnapshotAck.max_message_bytes(),
+        );
+    }
+
+    #[test]
+    fn handshakes_reject_malformed_bytes() {
+        fuzz(
+            &Handshake::current(0xdead_beef),
+            ClientChannel::Handshake.max_message_bytes(),
+        );
+    }
+
+    #[test]
+    fn handshake_responses_reject_malformed_bytes() {
+        let limit = ServerChannel::Handshake.max_message_bytes();
+
+        fuzz(
+            &HandshakeResponse::Accepted {
+                registries: RegistryTable {
+                    sprites: vec!["AK47".into(), "Skeleton".into()],
+                    equipment: vec!["AK47".into()],
+                    cards: vec!["Smite".into()],
+                },
+                tick_rate: 60.0,
+            },
+            limit,
+        );
+        fuzz(
+            &HandshakeResponse::Rejected {
+                reason: "Asset config differs from the server's.".to_string(),
+            },
+            limit,
+        );
+    }
+
+    #[test]
+    fn networked_entities_reject_malformed_bytes() {
+        let networked_entities = NetworkedEntities {
+            tick: 100,
+            baseline: Some(98),
+            last_processed_input: 12,
+            entities: (0..4)
+                .filter_map(|id| {
+                    EntityDelta::between(NetworkId(id), None, &quantized_entity(id as f32))
+                })
+                .collect(),
+            removed: vec![NetworkId(9)],
+        };
+
+        fuzz(
+            &networked_entities,
+            ServerChannel::NetworkedEntities.max_message_bytes(),
+        );
+    }
+
+    #[test]
+    fn server_message_envelopes_reject_malformed_bytes() {
+        let limit = ServerChannel::ServerMessages.max_message_bytes();
+        let envelope =
+            ServerMessageEnvelope::new(5, &ServerMessages::Warning("Slow down".to_string()));
+
+        fuzz(&envelope, limit);
+
+        // envelopes that decode carry payloads that must not panic either
+        let mut fuzzer = Fuzzer(0x2545_f491_4f6c_dd1d);
+        for _ in 0..512 {
+            let len = fuzzer.next() as usize % 64;
+            let envelope = ServerMessageEnvelope {
+                tick: 0,
+                kind: (fuzzer.next() % (ServerMessages::KINDS as u64 + 2)) as u16,
+                payload: fuzzer.bytes(len),
+            };
+
+            let _ = envelope.open(limit);
+        }
+    }
+}
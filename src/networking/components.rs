@@ -1,4 +1,5 @@
-use bevy::prelude::{Bundle, Component};
+use bevy::prelude::{Bundle, Component, Deref};
+use serde::{Deserialize, Serialize};
 
 use crate::enums::EntityState;
 
@@ -10,6 +11,17 @@ use crate::enums::EntityState;
 #[derive(Component, Default)]
 pub struct SyncedEntity;
 
+/**
+ * Network Id
+ *
+ * Stable id the server allocates for a networked entity,
+ * sent over the network in place of the server's `Entity`
+ */
+#[derive(
+    Component, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Deref,
+)]
+pub struct NetworkId(pub u64);
+
 /**
  * Networked Entity Bundle
  *
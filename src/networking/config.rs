@@ -1,5 +1,7 @@
 use bevy_renet::renet::ConnectionConfig;
 
+use crate::math::{extend_hash, hash_bytes};
+
 use super::channels::{ClientChannel, ServerChannel};
 
 pub fn connection_config() -> ConnectionConfig {
@@ -11,3 +13,25 @@ pub fn connection_config() -> ConnectionConfig {
 }
 
 pub const PROTOCOL_ID: u64 = 7;
+
+/// Version of the game protocol, exchanged in the handshake
+///
+/// Bump it whenever an existing message changes layout, appending a
+/// new `ServerMessages` variant does not require a bump
+pub const PROTOCOL_VERSION: u32 = 8;
+
+/// Identifies the game build, hashed from the crate version and
+/// the `COBALT_BUILD_ID` environment variable when it is set at compile time
+pub const BUILD_HASH: u64 = match option_env!("COBALT_BUILD_ID") {
+    Some(build_id) => extend_hash(
+        hash_bytes(env!("CARGO_PKG_VERSION").as_bytes()),
+        build_id.as_bytes(),
+    ),
+    None => hash_bytes(env!("CARGO_PKG_VERSION").as_bytes()),
+};
+
+/// Server simulation ticks per second, unless the server is started with another rate
+pub const TICK_RATE: f64 = 60.0;
+
+/// Highest tick rate a server may run at
+pub const MAX_TICK_RATE: f64 = 240.0;
@@ -1,15 +1,43 @@
 use bevy::prelude::*;
 
+use self::{resources::TickRate, sets::SimulationSet, systems::sync_fixed_timestep};
+
 pub mod channels;
+pub mod codec;
 pub mod components;
 pub mod config;
 pub mod models;
 pub mod networking;
+pub mod resources;
+pub mod sets;
+mod systems;
 
 pub struct NetworkingPlugin;
 
 impl Plugin for NetworkingPlugin {
-    fn build(&self, _app: &mut App) {}
+    fn build(&self, app: &mut App) {
+        let tick_rate = TickRate::default();
+        app.insert_resource(Time::<Fixed>::from_hz(*tick_rate));
+        app.insert_resource(tick_rate);
+
+        // the server's rate is only known once it is configured, or once the handshake is accepted
+        app.add_systems(
+            PreUpdate,
+            sync_fixed_timestep.run_if(resource_changed::<TickRate>()),
+        );
+
+        app.configure_sets(
+            FixedUpdate,
+            (
+                SimulationSet::Tick,
+                SimulationSet::Input,
+                SimulationSet::Physics,
+                SimulationSet::Gameplay,
+                SimulationSet::Sync,
+            )
+                .chain(),
+        );
+    }
 }
 
 pub fn is_server() -> impl Condition<()> {
@@ -1,16 +1,373 @@
-use bevy::prelude::Entity;
+use std::f32::consts::{PI, TAU};
+
+use bevy::math::{Vec2, Vec3};
+use bevy_renet::renet::transport::NETCODE_USER_DATA_BYTES;
+use bitflags::bitflags;
 use serde::Deserialize;
 use serde::Serialize;
 
-use crate::enums::EntityState;
+use crate::{
+    asset::registry::RegistryTable, enums::EntityState, input::resources::PlayerInput,
+    math::angle_between,
+};
+
+use super::{
+    components::NetworkId,
+    config::{BUILD_HASH, PROTOCOL_VERSION},
+};
+
+/// Quantized position steps per world unit
+///
+/// Positions are sent as `i16` steps, so only coordinates within
+/// ±`WORLD_EXTENT` (about ±4096 units) survive quantization
+pub const POSITION_PRECISION: f32 = 8.0;
+
+/// The largest coordinate a quantized position can hold, entities are kept
+/// inside it and the map config is checked against it
+pub const WORLD_EXTENT: f32 = i16::MAX as f32 / POSITION_PRECISION;
+
+/// Distance from the entity a quantized aim angle is expanded to
+pub const AIM_DISTANCE: f32 = 100.0;
 
 /// Serializable struct
 /// sent over the network to update clients of any
 /// synced entities
+///
+/// Entities are sent as deltas against the `baseline` snapshot
+/// the client last acknowledged, or in full when there is no baseline
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct NetworkedEntities {
-    pub entities: Vec<Entity>,
-    pub translations: Vec<[f32; 3]>,
-    pub aim_ats: Vec<[f32; 2]>,
-    pub states: Vec<EntityState>,
+    /// the server tick the snapshot was taken on
+    pub tick: u64,
+    /// the tick of the snapshot the deltas apply to
+    pub baseline: Option<u64>,
+    /// the sequence of the last input the server processed for the receiving client
+    pub last_processed_input: u32,
+    pub entities: Vec<EntityDelta>,
+    /// entities in the baseline that no longer exist
+    pub removed: Vec<NetworkId>,
+}
+
+/// Serializable struct
+/// sent by the client to acknowledge the latest snapshot it received
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct SnapshotAck {
+    pub tick: u64,
+}
+
+/// Serializable struct
+/// sent by the client right after connecting, the server
+/// only lets the client into the game when every field matches its own
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Handshake {
+    pub protocol_version: u32,
+    pub build_hash: u64,
+    pub asset_config_hash: u64,
+}
+
+impl Handshake {
+    /// The handshake of this build, with the hash of the loaded asset config
+    pub fn current(asset_config_hash: u64) -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            build_hash: BUILD_HASH,
+            asset_config_hash,
+        }
+    }
+
+    /// Compares a client's handshake against the server's, accepted
+    /// clients are sent the server's registry ids and tick rate
+    pub fn verify(
+        &self,
+        expected: &Handshake,
+        registries: RegistryTable,
+        tick_rate: f64,
+    ) -> HandshakeResponse {
+        let reason = if self.protocol_version != expected.protocol_version {
+            format!(
+                "Protocol version {} does not match the server's version {}.",
+                self.protocol_version, expected.protocol_version
+            )
+        } else if self.build_hash != expected.build_hash {
+            format!(
+                "Game build {:016x} does not match the server's build {:016x}.",
+                self.build_hash, expected.build_hash
+            )
+        } else if self.asset_config_hash != expected.asset_config_hash {
+            "Asset config differs from the server's.".to_string()
+        } else {
+            return HandshakeResponse::Accepted {
+                registries,
+                tick_rate,
+            };
+        };
+
+        HandshakeResponse::Rejected { reason }
+    }
+}
+
+/// Serializable enum
+/// sent by the server in answer to a `Handshake`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum HandshakeResponse {
+    Accepted {
+        registries: RegistryTable,
+        tick_rate: f64,
+    },
+    Rejected {
+        reason: String,
+    },
+}
+
+/// Player details carried in the netcode `user_data` of a connection
+///
+/// Signed by the token issuer when connecting with a connect token
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PlayerUserData {
+    pub id: u64,
+    pub name: String,
+}
+
+impl PlayerUserData {
+    pub const MAX_NAME_LENGTH: usize = 32;
+
+    /// Encodes the details, `None` when the name is too long
+    pub fn to_bytes(&self) -> Option<[u8; NETCODE_USER_DATA_BYTES]> {
+        if self.name.chars().count() > Self::MAX_NAME_LENGTH {
+            return None;
+        }
+
+        let encoded = bincode::serialize(self).ok()?;
+        let mut bytes = [0; NETCODE_USER_DATA_BYTES];
+        bytes.get_mut(..encoded.len())?.copy_from_slice(&encoded);
+
+        Some(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8; NETCODE_USER_DATA_BYTES]) -> Option<Self> {
+        bincode::deserialize::<Self>(bytes)
+            .ok()
+            .filter(|user_data| user_data.name.chars().count() <= Self::MAX_NAME_LENGTH)
+    }
+}
+
+bitflags! {
+    /// The movement buttons held for an input, packed into a byte
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct InputButtons: u8 {
+        const UP = 1;
+        const DOWN = 1 << 1;
+        const LEFT = 1 << 2;
+        const RIGHT = 1 << 3;
+    }
+}
+
+/// A player input, packed for the network
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct PackedInput {
+    pub sequence: u32,
+    pub buttons: InputButtons,
+    /// The aim world position, quantized like positions are
+    pub aim: [i16; 2],
+}
+
+impl From<&PlayerInput> for PackedInput {
+    fn from(player_input: &PlayerInput) -> Self {
+        let mut buttons = InputButtons::empty();
+        buttons.set(InputButtons::UP, player_input.up);
+        buttons.set(InputButtons::DOWN, player_input.down);
+        buttons.set(InputButtons::LEFT, player_input.left);
+        buttons.set(InputButtons::RIGHT, player_input.right);
+
+        Self {
+            sequence: player_input.sequence,
+            buttons,
+            aim: [
+                quantize_position(player_input.aim.x),
+                quantize_position(player_input.aim.y),
+            ],
+        }
+    }
+}
+
+impl PackedInput {
+    pub fn unpack(&self) -> PlayerInput {
+        PlayerInput {
+            sequence: self.sequence,
+            up: self.buttons.contains(InputButtons::UP),
+            down: self.buttons.contains(InputButtons::DOWN),
+            left: self.buttons.contains(InputButtons::LEFT),
+            right: self.buttons.contains(InputButtons::RIGHT),
+            aim: Vec2::new(
+                dequantize_position(self.aim[0]),
+                dequantize_position(self.aim[1]),
+            ),
+        }
+    }
+}
+
+/// Serializable struct
+/// sent by the client every tick over an unreliable channel
+///
+/// Carries the newest input along with the ones sent before it,
+/// oldest first, so a lost packet is covered by the next one
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct InputPacket {
+    pub inputs: Vec<PackedInput>,
+}
+
+impl InputPacket {
+    /// The most inputs a packet carries
+    pub const REDUNDANCY: usize = 4;
+}
+
+/// The full state of a synced entity, quantized for the network
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct QuantizedEntity {
+    pub translation: [i16; 2],
+    pub aim: u16,
+    pub state: EntityState,
+}
+
+impl QuantizedEntity {
+    pub fn new(translation: Vec3, aim: Vec2, state: EntityState) -> Self {
+        let from = Vec2::new(translation.x, translation.y);
+
+        Self {
+            translation: [
+                quantize_position(translation.x),
+                quantize_position(translation.y),
+            ],
+            aim: quantize_angle(angle_between(&from, &aim)),
+            state,
+        }
+    }
+
+    pub fn translation(&self) -> Vec3 {
+        Vec3::new(
+            dequantize_position(self.translation[0]),
+            dequantize_position(self.translation[1]),
+            0.0,
+        )
+    }
+
+    /// The aim as a world position, `AIM_DISTANCE` away along the aim angle
+    pub fn aim(&self) -> Vec2 {
+        let angle = dequantize_angle(self.aim);
+        let translation = self.translation();
+
+        Vec2::new(translation.x, translation.y) + Vec2::new(angle.cos(), angle.sin()) * AIM_DISTANCE
+    }
+}
+
+/// The fields of a synced entity that changed since the baseline
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct EntityDelta {
+    pub network_id: NetworkId,
+    pub translation: Option<[i16; 2]>,
+    pub aim: Option<u16>,
+    pub state: Option<EntityState>,
+}
+
+impl EntityDelta {
+    /// The delta from the baseline to the current state, `None` when nothing changed
+    pub fn between(
+        network_id: NetworkId,
+        baseline: Option<&QuantizedEntity>,
+        current: &QuantizedEntity,
+    ) -> Option<Self> {
+        let delta = Self {
+            network_id,
+            translation: (baseline.map(|b| b.translation) != Some(current.translation))
+                .then_some(current.translation),
+            aim: (baseline.map(|b| b.aim) != Some(current.aim)).then_some(current.aim),
+            state: (baseline.map(|b| b.state) != Some(current.state)).then_some(current.state),
+        };
+
+        if delta.translation.is_none() && delta.aim.is_none() && delta.state.is_none() {
+            return None;
+        }
+
+        Some(delta)
+    }
+
+    /// Applies the delta to the baseline, `None` when there is no baseline
+    /// and the delta does not carry every field
+    pub fn apply(&self, baseline: Option<&QuantizedEntity>) -> Option<QuantizedEntity> {
+        Some(QuantizedEntity {
+            translation: self.translation.or(baseline.map(|b| b.translation))?,
+            aim: self.aim.or(baseline.map(|b| b.aim))?,
+            state: self.state.or(baseline.map(|b| b.state))?,
+        })
+    }
+}
+
+/// Values outside ±`WORLD_EXTENT` are clamped to it
+pub fn quantize_position(value: f32) -> i16 {
+    (value * POSITION_PRECISION)
+        .round()
+        .clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
+
+pub fn dequantize_position(value: i16) -> f32 {
+    value as f32 / POSITION_PRECISION
+}
+
+pub fn quantize_angle(angle: f32) -> u16 {
+    ((angle + PI).rem_euclid(TAU) / TAU * u16::MAX as f32).round() as u16
+}
+
+pub fn dequantize_angle(value: u16) -> f32 {
+    value as f32 / u16::MAX as f32 * TAU - PI
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(x: f32, aim: Vec2, state: EntityState) -> QuantizedEntity {
+        QuantizedEntity::new(Vec3::new(x, -20.0, 0.0), aim, state)
+    }
+
+    #[test]
+    fn entity_deltas_round_trip() {
+        let baseline = entity(10.0, Vec2::new(100.0, 0.0), EntityState::Idle);
+        let currents = [
+            entity(12.5, Vec2::new(100.0, 0.0), EntityState::Idle),
+            entity(10.0, Vec2::new(0.0, 100.0), EntityState::Idle),
+            entity(10.0, Vec2::new(100.0, 0.0), EntityState::Walk),
+            entity(-300.0, Vec2::new(-50.0, 8.0), EntityState::Dead),
+        ];
+
+        for current in currents {
+            let delta = EntityDelta::between(NetworkId(1), Some(&baseline), &current).unwrap();
+            assert_eq!(delta.apply(Some(&baseline)), Some(current));
+
+            // without a baseline the delta carries every field
+            let full = EntityDelta::between(NetworkId(1), None, &current).unwrap();
+            assert_eq!(full.apply(None), Some(current));
+        }
+    }
+
+    #[test]
+    fn entity_deltas_only_carry_changed_fields() {
+        let baseline = entity(10.0, Vec2::new(100.0, 0.0), EntityState::Idle);
+        assert!(EntityDelta::between(NetworkId(1), Some(&baseline), &baseline).is_none());
+
+        let moved = entity(11.0, Vec2::new(100.0, 0.0), EntityState::Idle);
+        let delta = EntityDelta::between(NetworkId(1), Some(&baseline), &moved).unwrap();
+        assert!(delta.translation.is_some());
+        assert!(delta.state.is_none());
+        // a partial delta can't be applied without its baseline
+        assert!(delta.apply(None).is_none());
+    }
+
+    #[test]
+    fn positions_are_clamped_to_the_world_extent() {
+        assert_eq!(dequantize_position(quantize_position(1.5)), 1.5);
+        assert_eq!(
+            dequantize_position(quantize_position(WORLD_EXTENT * 2.0)),
+            WORLD_EXTENT
+        );
+    }
 }
@@ -1,8 +1,22 @@
-use crate::deck::keyword::events::{DamageEntityEvent, SpawnProjectileEvent};
+use crate::deck::{
+    card::equipment::events::{ChangeEquipmentEvent, EquipEquipmentEvent, ReloadEquipmentEvent},
+    events::HandEvent,
+    keyword::events::{
+        DamageEntityEvent, DespawnProjectileEvent, KeywordOutcomeEvent, SpawnProjectileEvent,
+    },
+};
 use bevy::prelude::Component;
 use serde::{Deserialize, Serialize};
 
-use crate::player::events::{CreatePlayerEvent, RemovePlayerEvent};
+use crate::{
+    player::events::{CreatePlayerEvent, RemovePlayerEvent, RespawnPlayerEvent},
+    round::events::MatchStateEvent,
+    score::events::{KillFeedEvent, ScoreboardEvent},
+    stats::events::StatusEffectsEvent,
+    team::events::PlayerTeamEvent,
+};
+
+use super::codec::{self, DecodeError};
 
 /**
  * Server Messages
@@ -14,5 +28,103 @@ pub enum ServerMessages {
     PlayerCreate(CreatePlayerEvent),
     PlayerRemove(RemovePlayerEvent),
     SpawnProjectile(SpawnProjectileEvent),
+    DespawnProjectile(DespawnProjectileEvent),
     DamageEntity(DamageEntityEvent),
+    ReloadEquipment(ReloadEquipmentEvent),
+    /// A warning shown to the player, sent before a player is kicked
+    Warning(String),
+    PlayerRespawn(RespawnPlayerEvent),
+    KillFeed(KillFeedEvent),
+    /// The full scoreboard, sent periodically while it changes
+    Scoreboard(ScoreboardEvent),
+    MatchState(MatchStateEvent),
+    PlayerTeam(PlayerTeamEvent),
+    ChangeEquipment(ChangeEquipmentEvent),
+    Hand(HandEvent),
+    EquipEquipment(EquipEquipmentEvent),
+    KeywordOutcome(KeywordOutcomeEvent),
+    StatusEffects(StatusEffectsEvent),
+}
+
+impl ServerMessages {
+    /// Number of message kinds this build knows, one per variant,
+    /// raise it with every appended variant, `kinds_counts_every_variant` checks it
+    pub const KINDS: u16 = 17;
+}
+
+/**
+ * Server Message Envelope
+ *
+ * The wire format of every message sent on the server messages channel
+ *
+ * | field   | type    | meaning                                        |
+ * |---------|---------|------------------------------------------------|
+ * | tick    | u64     | server tick the message was sent on            |
+ * | kind    | u16     | index of the `ServerMessages` variant          |
+ * | payload | Vec<u8> | the variant's fields, bincode encoded          |
+ *
+ * The schema only evolves additively: new variants are appended to
+ * `ServerMessages` and `KINDS` is raised, so older clients skip kinds they
+ * don't know instead of failing to decode. Reordering variants or changing
+ * the fields of an existing one requires bumping `PROTOCOL_VERSION`, which
+ * makes the handshake turn mismatched clients away.
+ */
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerMessageEnvelope {
+    pub tick: u64,
+    pub kind: u16,
+    pub payload: Vec<u8>,
+}
+
+impl ServerMessageEnvelope {
+    pub fn new(tick: u64, message: &ServerMessages) -> Self {
+        // bincode leads an enum with its variant index as a u32,
+        // which becomes the kind while the rest is the payload
+        let mut payload = codec::encode_payload(message);
+        let index = u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]);
+        payload.drain(..4);
+
+        Self {
+            tick,
+            kind: index as u16,
+            payload,
+        }
+    }
+
+    /// Decodes the message, returning `None` for kinds this build doesn't know
+    pub fn open(&self, limit: usize) -> Result<Option<ServerMessages>, DecodeError> {
+        if self.kind >= ServerMessages::KINDS {
+            return Ok(None);
+        }
+
+        let mut bytes = (self.kind as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&self.payload);
+
+        codec::decode_payload(&bytes, limit).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes a message of the kind, with no payload
+    fn open_empty(kind: u16) -> Result<ServerMessages, DecodeError> {
+        codec::decode_payload(&(kind as u32).to_le_bytes(), 64)
+    }
+
+    fn is_unknown_variant(result: &Result<ServerMessages, DecodeError>) -> bool {
+        matches!(
+            result,
+            Err(DecodeError::Malformed(error)) if matches!(**error, bincode::ErrorKind::Custom(_))
+        )
+    }
+
+    #[test]
+    fn kinds_counts_every_variant() {
+        // serde rejects an index past the last variant, while the last variant
+        // only fails on its missing payload
+        assert!(!is_unknown_variant(&open_empty(ServerMessages::KINDS - 1)));
+        assert!(is_unknown_variant(&open_empty(ServerMessages::KINDS)));
+    }
 }
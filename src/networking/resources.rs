@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use bevy::prelude::{Deref, Entity, Resource};
+
+use super::{
+    components::NetworkId,
+    config::{MAX_TICK_RATE, TICK_RATE},
+};
+
+/// Bidirectional map of network ids and local entities
+///
+/// The server allocates the ids, clients register the ids
+/// they receive against the entities they spawn for them.
+#[derive(Debug, Default, Resource)]
+pub struct NetworkRegistry {
+    last_id: u64,
+    entities: HashMap<NetworkId, Entity>,
+    network_ids: HashMap<Entity, NetworkId>,
+}
+
+impl NetworkRegistry {
+    /// Allocates a new network id, ids are never reused
+    pub fn allocate(&mut self) -> NetworkId {
+        self.last_id += 1;
+        NetworkId(self.last_id)
+    }
+
+    pub fn insert(&mut self, network_id: NetworkId, entity: Entity) {
+        self.entities.insert(network_id, entity);
+        self.network_ids.insert(entity, network_id);
+    }
+
+    pub fn entity(&self, network_id: &NetworkId) -> Option<Entity> {
+        self.entities.get(network_id).copied()
+    }
+
+    pub fn network_id(&self, entity: &Entity) -> Option<NetworkId> {
+        self.network_ids.get(entity).copied()
+    }
+
+    pub fn remove(&mut self, network_id: &NetworkId) -> Option<Entity> {
+        let entity = self.entities.remove(network_id)?;
+        self.network_ids.remove(&entity);
+        Some(entity)
+    }
+
+    pub fn remove_entity(&mut self, entity: &Entity) -> Option<NetworkId> {
+        let network_id = self.network_ids.remove(entity)?;
+        self.entities.remove(&network_id);
+        Some(network_id)
+    }
+}
+
+/// Server simulation ticks per second
+///
+/// The server sets it from its settings, clients adopt the server's
+/// rate once their handshake is accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Deref)]
+pub struct TickRate(pub f64);
+
+impl TickRate {
+    /// Whether a server could be running at this rate
+    pub fn is_valid(&self) -> bool {
+        self.0.is_finite() && self.0 > 0.0 && self.0 <= MAX_TICK_RATE
+    }
+}
+
+impl Default for TickRate {
+    fn default() -> Self {
+        Self(TICK_RATE)
+    }
+}
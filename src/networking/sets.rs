@@ -0,0 +1,19 @@
+use bevy::ecs::schedule::SystemSet;
+
+/// The stages of a fixed simulation tick, run in order in `FixedUpdate`
+///
+/// Shared by the server and the client, so the client predicts
+/// with the same timestep the server simulates with
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SimulationSet {
+    /// Advances the tick counter
+    Tick,
+    /// Applies player input to velocities
+    Input,
+    /// Moves entities by their velocities
+    Physics,
+    /// Equipment, projectiles and damage
+    Gameplay,
+    /// Sends the results of the tick
+    Sync,
+}
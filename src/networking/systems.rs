@@ -0,0 +1,11 @@
+use bevy::{
+    ecs::system::{Res, ResMut},
+    time::{Fixed, Time},
+};
+
+use super::resources::TickRate;
+
+/// Steps the fixed timestep at the tick rate
+pub fn sync_fixed_timestep(tick_rate: Res<TickRate>, mut fixed_time: ResMut<Time<Fixed>>) {
+    fixed_time.set_timestep_hz(**tick_rate);
+}
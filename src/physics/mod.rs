@@ -1,9 +1,9 @@
 use bevy::{
-    app::{App, Plugin, Update},
+    app::{App, FixedUpdate, Plugin, Update},
     ecs::schedule::{common_conditions::in_state, IntoSystemConfigs},
 };
 
-use crate::enums::GameState;
+use crate::{enums::GameState, networking::sets::SimulationSet};
 
 use self::systems::{apply_direction, apply_velocity};
 
@@ -14,9 +14,15 @@ pub struct PhysicsPlugin;
 
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            apply_velocity
+                .in_set(SimulationSet::Physics)
+                .run_if(in_state(GameState::Gameloop)),
+        );
         app.add_systems(
             Update,
-            (apply_velocity, apply_direction).run_if(in_state(GameState::Gameloop)),
+            apply_direction.run_if(in_state(GameState::Gameloop)),
         );
     }
 }
@@ -8,6 +8,7 @@ use bevy::{
 use crate::{
     input::components::Aim,
     math::{angle_between, vec2_from_vec3},
+    networking::models::WORLD_EXTENT,
 };
 
 use super::components::Velocity;
@@ -16,6 +17,10 @@ pub fn apply_velocity(dt: Res<Time>, mut query: Query<(&mut Transform, &Velocity
     for (mut transform, vel) in &mut query {
         transform.translation.x += vel.vector.x * dt.delta_seconds();
         transform.translation.y += vel.vector.y * dt.delta_seconds();
+
+        // positions beyond the extent can't be sent over the network
+        transform.translation.x = transform.translation.x.clamp(-WORLD_EXTENT, WORLD_EXTENT);
+        transform.translation.y = transform.translation.y.clamp(-WORLD_EXTENT, WORLD_EXTENT);
     }
 }
 
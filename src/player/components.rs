@@ -1,18 +1,23 @@
+use std::{collections::VecDeque, fmt};
+
 use bevy::{
-    prelude::{Bundle, Component, Deref, Handle, Transform, Vec2},
+    prelude::{Bundle, Component, Deref, DerefMut, Handle, Transform, Vec2},
     sprite::{SpriteSheetBundle, TextureAtlas},
+    time::Timer,
 };
 use bevy_2d_collisions::components::{CollisionBox, CollisionBundle, CollisionGroup};
 use bevy_renet::renet::ClientId;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     animation::components::{Animated2DObjectBundle, Animator},
     body::components::Object2DBundle,
+    client::components::SnapshotBuffer,
     enums::CollisionGroups,
-    input::components::Aim,
+    input::components::{Aim, CommandQueue, InputQueue},
     networking::components::NetworkedEntityBundle,
     physics::components::{AnimatedKineticBodyBundle, KineticBodyBundle},
-    stats::components::Health,
+    stats::components::{Health, StatusEffects},
 };
 
 /**
@@ -36,10 +41,66 @@ impl Default for Player {
 /**
 * Team
 *
-* The team the player is on
+* The team the player is on, spectators watch without playing
 */
-#[derive(Component, Deref, Default)]
-pub struct Team(pub CollisionGroups);
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Team {
+    #[default]
+    Spectator,
+    /// One of the teams, numbered from 0
+    Index(u8),
+    /// Plays against everyone else
+    FreeForAll,
+}
+
+impl Team {
+    /// The most teams a match can have, each takes a collision layer bit
+    pub const MAX: u8 = 8;
+
+    /// The team's collision layer bit, spectators have none
+    pub fn layer(&self) -> u32 {
+        let first = CollisionGroups::TeamAlpha as u32;
+        match self {
+            Team::Spectator => 0,
+            Team::Index(index) => first << (*index).min(Self::MAX - 1),
+            Team::FreeForAll => first << Self::MAX,
+        }
+    }
+
+    /// The layers the team's projectiles hit when aimed at enemies
+    pub fn enemy_mask(&self) -> u32 {
+        match self {
+            Team::Spectator => 0,
+            Team::Index(_) => {
+                let teams = (0..Self::MAX).fold(0, |mask, index| mask | Team::Index(index).layer());
+                teams & !self.layer()
+            }
+            Team::FreeForAll => self.layer(),
+        }
+    }
+
+    /// The layers the team's projectiles hit when aimed at teammates
+    pub fn ally_mask(&self) -> u32 {
+        match self {
+            Team::Index(_) => self.layer(),
+            Team::Spectator | Team::FreeForAll => 0,
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        *self != Team::Spectator
+    }
+}
+
+impl fmt::Display for Team {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Team::Spectator => write!(f, "Spectators"),
+            Team::Index(index) => write!(f, "Team {}", index + 1),
+            Team::FreeForAll => write!(f, "Free for all"),
+        }
+    }
+}
 
 /**
  * Death State
@@ -47,6 +108,22 @@ pub struct Team(pub CollisionGroups);
 #[derive(Component, Debug, Default)]
 pub struct Death;
 
+/**
+ * Respawn
+ *
+ * Counts down until a dead player respawns
+ */
+#[derive(Component, Debug, Deref, DerefMut)]
+pub struct Respawn(pub Timer);
+
+/**
+ * Spawn Protection
+ *
+ * A freshly spawned player can't be damaged until the timer finishes
+ */
+#[derive(Component, Debug, Deref, DerefMut)]
+pub struct SpawnProtection(pub Timer);
+
 /**
  * Player Bundle
  *
@@ -59,10 +136,14 @@ pub struct PlayerBundle {
 
     pub health: Health,
 
+    pub status_effects: StatusEffects,
+
     pub team: Team,
 
     pub aim: Aim,
 
+    pub snapshot_buffer: SnapshotBuffer,
+
     pub kinetic_body: AnimatedKineticBodyBundle,
 
     pub network_entity: NetworkedEntityBundle,
@@ -108,17 +189,65 @@ impl PlayerBundle {
  * Server Player Bundle
  *
  */
+/// A player's hitbox on a past server tick
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HitboxSample {
+    pub tick: u64,
+    pub translation: Vec2,
+    pub size: Vec2,
+}
+
+/**
+ * Hitbox History
+ *
+ * The player's hitbox over the last server ticks, used to
+ * evaluate hits against where a client saw the player
+ */
+#[derive(Component, Debug, Default)]
+pub struct HitboxHistory {
+    pub samples: VecDeque<HitboxSample>,
+}
+
+impl HitboxHistory {
+    pub fn record(&mut self, sample: HitboxSample, capacity: usize) {
+        while self.samples.len() >= capacity.max(1) {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(sample);
+    }
+
+    /// The hitbox on the tick, or the closest recorded tick before it.
+    /// Ticks older than the history fall back to the oldest sample
+    pub fn at(&self, tick: u64) -> Option<HitboxSample> {
+        self.samples
+            .iter()
+            .rev()
+            .find(|sample| sample.tick <= tick)
+            .or(self.samples.front())
+            .copied()
+    }
+}
+
 #[derive(Bundle, Default)]
 pub struct ServerPlayerBundle {
     pub player: Player,
 
     pub health: Health,
 
+    pub status_effects: StatusEffects,
+
     pub team: Team,
 
     pub kinetic_body: KineticBodyBundle,
 
     pub network_entity: NetworkedEntityBundle,
+
+    pub hitbox_history: HitboxHistory,
+
+    pub input_queue: InputQueue,
+
+    pub command_queue: CommandQueue,
 }
 
 impl ServerPlayerBundle {
@@ -1,15 +1,32 @@
-use bevy::{
-    ecs::entity::Entity,
-    prelude::{Component, Event, Vec2},
-};
+use bevy::prelude::{Component, Event, Vec2};
 use serde::{Deserialize, Serialize};
 
-use crate::client::resources::ClientId;
+use crate::{client::resources::ClientId, networking::components::NetworkId};
 
-#[derive(Debug, Serialize, Deserialize, Component, Event)]
+use super::components::Team;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Component, Event)]
 pub enum PlayerCommand {
-    UseEquipment { cast_at: Vec2 },
-    // ChangeEquipment { equipment: Entity },
+    /// `render_tick` is the server tick the client was rendering remote players at
+    UseEquipment {
+        cast_at: Vec2,
+        render_tick: u64,
+    },
+    Reload,
+    /// Asks to switch teams, any playing team joins free-for-all in a free-for-all match
+    JoinTeam {
+        team: Team,
+    },
+    /// Asks to put the equipment in an inventory slot in hand
+    ChangeEquipment {
+        slot: u8,
+    },
+    /// Asks to play the card in a hand slot, on another player
+    /// or on the player themselves when there is no target
+    PlayCard {
+        slot: u8,
+        target: Option<NetworkId>,
+    },
 }
 
 /**
@@ -21,10 +38,10 @@ pub enum PlayerCommand {
 */
 #[derive(Event, Debug, Serialize, Deserialize)]
 pub struct CreatePlayerEvent {
-    pub entity: Entity,
+    pub network_id: NetworkId,
     pub id: ClientId,
     pub translation: [f32; 3],
-    pub team: u32,
+    pub team: Team,
 }
 
 /**
@@ -37,3 +54,16 @@ pub struct CreatePlayerEvent {
 pub struct RemovePlayerEvent {
     pub id: ClientId,
 }
+
+/**
+ * Player Respawn Event
+ *
+ * A Bevy Event to inform client systems
+ * a dead player has respawned, contains the corresponding
+ * server message
+ */
+#[derive(Event, Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct RespawnPlayerEvent {
+    pub network_id: NetworkId,
+    pub translation: [f32; 3],
+}
@@ -1,10 +1,18 @@
 use bevy::prelude::*;
 
-use crate::{client::sets::Connected, enums::GameState, input::resources::PlayerInput};
+use crate::{
+    client::sets::Connected,
+    enums::GameState,
+    input::resources::PlayerInput,
+    networking::{is_client, is_server, sets::SimulationSet},
+};
 
 use self::{
-    events::{CreatePlayerEvent, PlayerCommand, RemovePlayerEvent},
-    systems::{create_player, player_despawn},
+    events::{CreatePlayerEvent, PlayerCommand, RemovePlayerEvent, RespawnPlayerEvent},
+    systems::{
+        create_player, on_player_respawn, player_despawn, refill_equipment_on_respawn,
+        respawn_system, start_respawn_timer, tick_spawn_timers,
+    },
 };
 
 pub mod components;
@@ -17,13 +25,34 @@ impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            (create_player, player_despawn)
+            (
+                create_player,
+                player_despawn,
+                on_player_respawn,
+                refill_equipment_on_respawn,
+            )
+                .run_if(is_client())
                 .run_if(in_state(GameState::Gameloop))
                 .in_set(Connected),
         );
 
+        app.add_systems(
+            FixedUpdate,
+            (
+                start_respawn_timer,
+                tick_spawn_timers,
+                respawn_system,
+                refill_equipment_on_respawn,
+            )
+                .chain()
+                .in_set(SimulationSet::Gameplay)
+                .run_if(in_state(GameState::Gameloop))
+                .run_if(is_server()),
+        );
+
         app.add_event::<CreatePlayerEvent>();
         app.add_event::<RemovePlayerEvent>();
+        app.add_event::<RespawnPlayerEvent>();
         app.add_event::<PlayerCommand>();
 
         app.insert_resource(PlayerInput::default());
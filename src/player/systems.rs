@@ -1,23 +1,30 @@
-use crate::deck::card::equipment::components::EquipmentBundle;
+use crate::deck::card::equipment::components::{EquipmentBundle, Equipped, Inventory};
 use crate::input::components::{Controllable, PlayerCamera};
-use crate::player::components::PlayerBundle;
+use crate::player::components::{Death, Player, PlayerBundle, Respawn, SpawnProtection, Team};
 use bevy::prelude::*;
 use bevy::sprite::TextureAtlas;
 use bevy_2d_collisions::components::CollisionGroup;
 use bevy_health_bar::ProgressBarBundle;
 
 use crate::animation::components::Animator;
-use crate::asset::enums::{Equipment, Sprites};
+use crate::animation::events::PlayAnimationEvent;
 use crate::asset::resources::{AssetHandler, AssetsConfig};
-use crate::client::resources::{ClientLobby, CurrentClientId, NetworkEntities, PlayerInfo};
-use crate::enums::CollisionGroups;
-use crate::player::events::{CreatePlayerEvent, RemovePlayerEvent};
+use crate::client::components::SnapshotBuffer;
+use crate::client::resources::{ClientLobby, CurrentClientId, PlayerInfo, PredictionHistory};
+use crate::enums::{CollisionGroups, EntityState};
+use crate::networking::components::NetworkId;
+use crate::networking::networking::ServerMessages;
+use crate::networking::resources::NetworkRegistry;
+use crate::player::events::{CreatePlayerEvent, RemovePlayerEvent, RespawnPlayerEvent};
+use crate::round::resources::MatchState;
+use crate::server::{events::SyncEntityEvent, params::ServerMessenger};
+use crate::stats::components::Health;
 
 pub fn create_player(
     mut commands: Commands,
     mut reader_player_create: EventReader<CreatePlayerEvent>,
     mut lobby: ResMut<ClientLobby>,
-    mut network_mapping: ResMut<NetworkEntities>,
+    mut network_registry: ResMut<NetworkRegistry>,
     client_id: Res<CurrentClientId>,
     asset_handler: Res<AssetHandler>,
     asset_config: Res<AssetsConfig>,
@@ -26,6 +33,14 @@ pub fn create_player(
     asset_server: Res<AssetServer>,
 ) {
     for player_create_event in reader_player_create.read() {
+        // the server may announce a player again, when it sends the full state on join
+        if network_registry
+            .entity(&player_create_event.network_id)
+            .is_some()
+        {
+            continue;
+        }
+
         println!("Player {} connected.", player_create_event.id.0);
 
         // TODO: Move this to a better camera system that allows for targets
@@ -36,11 +51,11 @@ pub fn create_player(
             commands.spawn((camera_bundle, PlayerCamera));
         }
 
-        let character_type = Sprites::Skeleton;
+        let character_type = &asset_config.sprites.character;
         // Retrieve character assets from the already loaded resources
         let (texture, animations, hitbox_config) = asset_handler
             .textures
-            .get(&character_type)
+            .get(character_type)
             .expect("unexpected character requested.");
 
         let hitbox_config = hitbox_config.expect(&format!(
@@ -49,21 +64,30 @@ pub fn create_player(
         ));
 
         // Spawn Player
-        let mut player_entity = commands.spawn(PlayerBundle::new(
-            bevy_renet::renet::ClientId::from_raw(*player_create_event.id),
-            Animator::import(animations),
-            texture_atlases.add(texture.clone()),
-            Transform::from_xyz(
-                player_create_event.translation[0],
-                player_create_event.translation[1],
-                player_create_event.translation[2],
+        let mut player_entity = commands.spawn((
+            PlayerBundle::new(
+                bevy_renet::renet::ClientId::from_raw(*player_create_event.id),
+                Animator::import(animations),
+                texture_atlases.add(texture.clone()),
+                Transform::from_xyz(
+                    player_create_event.translation[0],
+                    player_create_event.translation[1],
+                    player_create_event.translation[2],
+                ),
+                Vec2::new(hitbox_config.width, hitbox_config.height),
+                CollisionGroup {
+                    layer: CollisionGroups::Player as u32 | player_create_event.team.layer(),
+                    mask: 0,
+                },
             ),
-            Vec2::new(hitbox_config.width, hitbox_config.height),
-            CollisionGroup {
-                layer: CollisionGroups::Player as u32 | player_create_event.team,
-                mask: 0,
-            },
+            player_create_event.network_id,
         ));
+        player_entity.insert(player_create_event.team);
+
+        // spectators watch without being seen
+        if !player_create_event.team.is_playing() {
+            player_entity.insert(Visibility::Hidden);
+        }
 
         // if this is the client player, give them control
         if player_create_event.id.0 == client_id.0 {
@@ -74,37 +98,52 @@ pub fn create_player(
 
         // Add player to network mapping
         let player_info = PlayerInfo {
-            server_entity: player_create_event.entity,
+            network_id: player_create_event.network_id,
             client_entity: player_entity.id(),
         };
 
         lobby.players.insert(player_create_event.id, player_info);
-        network_mapping
-            .0
-            .insert(player_create_event.entity, player_entity.id());
+        network_registry.insert(player_create_event.network_id, player_entity.id());
 
         let player_entity = player_entity.id();
 
-        // Retrieve character assets from the already loaded resources
-        let (texture, animations, _hitbox_config) = asset_handler
-            .textures
-            .get(&Sprites::AK47)
-            .expect("unexpected character requested.");
-
-        // Spawn Equipment
-        commands
-            .spawn(EquipmentBundle::new(
-                asset_config
+        // Spawn Equipment, one per inventory slot with only the first one in hand
+        let slots = asset_config
+            .inventory
+            .loadout
+            .iter()
+            .enumerate()
+            .map(|(slot, equipment)| {
+                let stats = asset_config
                     .stats
                     .equipment
-                    .get(&Equipment::AK47)
-                    .expect("Could not find AK47 in equipment config.")
-                    .into(),
-                Animator::import(animations),
-                texture_atlases.add(texture.clone()),
-                Transform::from_xyz(5.0, -1.5, 0.0),
-            ))
-            .set_parent(player_entity);
+                    .get(equipment)
+                    .unwrap_or_else(|| {
+                        panic!("Could not find {:?} in equipment config.", equipment)
+                    });
+
+                // Retrieve equipment assets from the already loaded resources
+                let (texture, animations, _hitbox_config) = asset_handler
+                    .textures
+                    .get(&stats.sprite)
+                    .expect("unexpected equipment requested.");
+
+                let mut equipment_entity = commands.spawn(EquipmentBundle::new(
+                    stats.into(),
+                    Animator::import(animations),
+                    texture_atlases.add(texture.clone()),
+                    Transform::from_xyz(5.0, -1.5, 0.0),
+                ));
+                equipment_entity.set_parent(player_entity);
+
+                if slot != 0 {
+                    equipment_entity.insert(Visibility::Hidden);
+                }
+
+                equipment_entity.id()
+            })
+            .collect();
+        commands.entity(player_entity).insert(Inventory::new(slots));
 
         // Spawn Health Bar
         let transform = Transform::from_xyz(-15.0, 19.0, 0.0).with_scale(Vec3::new(0.5, 0.5, 0.5));
@@ -121,14 +160,159 @@ pub fn player_despawn(
     mut commands: Commands,
     mut reader_player_remove: EventReader<RemovePlayerEvent>,
     mut lobby: ResMut<ClientLobby>,
-    mut network_mapping: ResMut<NetworkEntities>,
+    mut network_registry: ResMut<NetworkRegistry>,
 ) {
     for player_remove_event in reader_player_remove.read() {
         println!("Player {} disconnected.", player_remove_event.id.0);
 
         if let Some(player_info) = lobby.players.remove(&player_remove_event.id) {
-            commands.entity(player_info.client_entity).despawn();
-            network_mapping.0.remove(&player_info.server_entity);
+            commands
+                .entity(player_info.client_entity)
+                .despawn_recursive();
+            network_registry.remove(&player_info.network_id);
+        }
+    }
+}
+
+/// Starts the respawn countdown of players that died,
+/// unless the match makes them wait for the next round
+pub fn start_respawn_timer(
+    mut commands: Commands,
+    asset_config: Res<AssetsConfig>,
+    match_state: Res<MatchState>,
+    query: Query<Entity, (Added<Death>, With<Player>)>,
+) {
+    if !match_state.allows_respawn(&asset_config.match_rules) {
+        return;
+    }
+
+    for entity in &query {
+        commands.entity(entity).insert(Respawn(Timer::from_seconds(
+            asset_config.map.respawn_delay,
+            TimerMode::Once,
+        )));
+    }
+}
+
+pub fn tick_spawn_timers(
+    mut commands: Commands,
+    dt: Res<Time>,
+    mut respawn_query: Query<&mut Respawn>,
+    mut protection_query: Query<(Entity, &mut SpawnProtection)>,
+) {
+    for mut respawn in &mut respawn_query {
+        respawn.tick(dt.delta());
+    }
+
+    for (entity, mut spawn_protection) in &mut protection_query {
+        if spawn_protection.tick(dt.delta()).finished() {
+            commands.entity(entity).remove::<SpawnProtection>();
+        }
+    }
+}
+
+/// Respawns players whose respawn timer finished at one of their team's spawn points,
+/// with full health, a full magazine and a brief spawn protection
+pub fn respawn_system(
+    mut commands: Commands,
+    mut server_messenger: ServerMessenger,
+    mut writer_sync_entity: EventWriter<SyncEntityEvent>,
+    mut writer_player_respawn: EventWriter<RespawnPlayerEvent>,
+    mut spawn_count: Local<u64>,
+    asset_config: Res<AssetsConfig>,
+    mut query: Query<(
+        Entity,
+        &Respawn,
+        &Team,
+        &NetworkId,
+        &mut Transform,
+        &mut Health,
+        &mut EntityState,
+    )>,
+) {
+    for (entity, respawn, team, network_id, mut transform, mut health, mut entity_state) in
+        &mut query
+    {
+        if !respawn.finished() {
+            continue;
+        }
+
+        // spawn points are taken in turn so players don't spawn on top of each other
+        transform.translation = asset_config.map.spawn_point(*team, *spawn_count);
+        *spawn_count += 1;
+
+        health.current = health.max;
+        *entity_state = EntityState::Idle;
+
+        commands
+            .entity(entity)
+            .remove::<(Death, Respawn)>()
+            .insert(SpawnProtection(Timer::from_seconds(
+                asset_config.map.spawn_protection,
+                TimerMode::Once,
+            )));
+
+        let respawn_player_event = RespawnPlayerEvent {
+            network_id: *network_id,
+            translation: transform.translation.to_array(),
+        };
+        server_messenger.broadcast(ServerMessages::PlayerRespawn(respawn_player_event));
+        writer_player_respawn.send(respawn_player_event);
+        writer_sync_entity.send(SyncEntityEvent { entity });
+    }
+}
+
+pub fn on_player_respawn(
+    mut commands: Commands,
+    mut reader_player_respawn: EventReader<RespawnPlayerEvent>,
+    mut writer_play_animation: EventWriter<PlayAnimationEvent>,
+    mut prediction_history: ResMut<PredictionHistory>,
+    network_registry: Res<NetworkRegistry>,
+    mut query: Query<(&mut Transform, &mut Health, &mut EntityState)>,
+    mut snapshot_query: Query<(&mut SnapshotBuffer, Has<Controllable>)>,
+) {
+    for player_respawn in reader_player_respawn.read() {
+        let Some(entity) = network_registry.entity(&player_respawn.network_id) else {
+            continue;
+        };
+        let Ok((mut transform, mut health, mut entity_state)) = query.get_mut(entity) else {
+            continue;
+        };
+
+        transform.translation = player_respawn.translation.into();
+        health.current = health.max;
+        *entity_state = EntityState::Idle;
+
+        // the player moved instantly, there is nothing to interpolate or replay from
+        if let Ok((mut snapshot_buffer, controllable)) = snapshot_query.get_mut(entity) {
+            snapshot_buffer.snapshots.clear();
+
+            if controllable {
+                prediction_history.states.clear();
+            }
+        }
+
+        commands.entity(entity).remove::<Death>();
+        writer_play_animation.send(PlayAnimationEvent::new(entity, "Idle"));
+    }
+}
+
+/// Refills the equipment of respawned players, on the server and the clients
+pub fn refill_equipment_on_respawn(
+    mut reader_player_respawn: EventReader<RespawnPlayerEvent>,
+    mut equipment_query: Query<&mut Equipped>,
+    network_registry: Res<NetworkRegistry>,
+    children_query: Query<&Children>,
+) {
+    for player_respawn in reader_player_respawn.read() {
+        let Some(entity) = network_registry.entity(&player_respawn.network_id) else {
+            continue;
+        };
+
+        for child in children_query.iter_descendants(entity) {
+            if let Ok(mut equipped) = equipment_query.get_mut(child) {
+                equipped.equipment.reload();
+            }
         }
     }
 }
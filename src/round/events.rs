@@ -0,0 +1,33 @@
+use bevy::prelude::Event;
+use serde::{Deserialize, Serialize};
+
+use super::resources::{Contender, MatchPhase};
+
+/**
+ * Match State Event
+ *
+ * A Bevy Event to inform client systems
+ * the match changed phase, contains the corresponding
+ * server message
+ */
+#[derive(Event, Debug, Clone, Serialize, Deserialize)]
+pub struct MatchStateEvent {
+    pub phase: MatchPhase,
+    pub round: u32,
+    /// Seconds left in the phase
+    pub remaining: f32,
+    pub round_wins: Vec<(Contender, u32)>,
+}
+
+/**
+ * Reset World Event
+ *
+ * A Bevy Event to inform server systems
+ * the players should be respawned and the projectiles cleared,
+ * sent when a round or a warmup starts
+ */
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ResetWorldEvent {
+    /// Whether a new match begins, which also resets the scoreboard
+    pub new_match: bool,
+}
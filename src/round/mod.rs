@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+
+use crate::{
+    client::sets::Connected,
+    enums::GameState,
+    networking::{is_client, is_server, sets::SimulationSet},
+};
+
+use self::{
+    events::{MatchStateEvent, ResetWorldEvent},
+    resources::{MatchSeed, MatchState},
+    systems::{
+        count_round_kills, match_phase_system, on_match_state, reset_world_system,
+        send_match_state, tick_match_timer,
+    },
+};
+
+pub mod events;
+pub mod resources;
+mod systems;
+
+pub struct RoundPlugin;
+
+impl Plugin for RoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (count_round_kills, match_phase_system, reset_world_system)
+                .chain()
+                .in_set(SimulationSet::Gameplay)
+                .run_if(is_server())
+                .run_if(in_state(GameState::Gameloop)),
+        );
+        app.add_systems(
+            Update,
+            send_match_state
+                .run_if(is_server())
+                .run_if(in_state(GameState::Gameloop)),
+        );
+
+        app.add_systems(
+            Update,
+            (on_match_state, tick_match_timer)
+                .chain()
+                .run_if(is_client())
+                .run_if(in_state(GameState::Gameloop))
+                .in_set(Connected),
+        );
+
+        app.add_event::<MatchStateEvent>();
+        app.add_event::<ResetWorldEvent>();
+
+        app.insert_resource(MatchState::default());
+        app.insert_resource(MatchSeed::default());
+    }
+}
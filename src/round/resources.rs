@@ -0,0 +1,189 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use bevy::{
+    prelude::{Deref, Resource},
+    time::{Timer, TimerMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset::resources::MatchConfig,
+    client::resources::ClientId,
+    math::{extend_hash, hash_bytes},
+    networking::components::NetworkId,
+    player::components::Team,
+};
+
+use super::events::MatchStateEvent;
+
+/// Who competes for the win, a team or a player on their own in free-for-all
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Contender {
+    Team(u8),
+    Player(ClientId),
+}
+
+impl Contender {
+    /// What a player on a team competes as, spectators don't compete
+    pub fn of(team: Team, client_id: u64) -> Option<Self> {
+        match team {
+            Team::Spectator => None,
+            Team::Index(index) => Some(Contender::Team(index)),
+            Team::FreeForAll => Some(Contender::Player(ClientId(client_id))),
+        }
+    }
+}
+
+/// The phases a match goes through, the winners are `None` on a draw
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchPhase {
+    /// Players wait for enough others to join, kills don't count
+    #[default]
+    Warmup,
+    InProgress,
+    RoundEnd {
+        winner: Option<Contender>,
+    },
+    MatchEnd {
+        winner: Option<Contender>,
+    },
+}
+
+/// The state of the current match, run by the server and mirrored by the clients
+#[derive(Debug, Resource)]
+pub struct MatchState {
+    pub phase: MatchPhase,
+    /// Counts down the current phase
+    pub timer: Timer,
+    /// The current round, 0 during warmup
+    pub round: u32,
+    /// Kills of each contender in the current round
+    pub round_kills: HashMap<Contender, u32>,
+    /// Rounds won by each contender in the current match
+    pub round_wins: HashMap<Contender, u32>,
+}
+
+impl Default for MatchState {
+    fn default() -> Self {
+        Self {
+            phase: MatchPhase::Warmup,
+            timer: Timer::default(),
+            round: 0,
+            round_kills: HashMap::new(),
+            round_wins: HashMap::new(),
+        }
+    }
+}
+
+impl MatchState {
+    /// Moves to a phase and starts its countdown
+    pub fn enter(&mut self, phase: MatchPhase, config: &MatchConfig) {
+        let duration = match phase {
+            MatchPhase::Warmup => {
+                self.round = 0;
+                self.round_wins.clear();
+                config.warmup
+            }
+            MatchPhase::InProgress => {
+                self.round += 1;
+                self.round_kills.clear();
+                config.round_time
+            }
+            MatchPhase::RoundEnd { winner } => {
+                if let Some(winner) = winner {
+                    *self.round_wins.entry(winner).or_default() += 1;
+                }
+                config.round_end_time
+            }
+            MatchPhase::MatchEnd { .. } => config.match_end_time,
+        };
+
+        self.phase = phase;
+        self.timer = Timer::from_seconds(duration, TimerMode::Once);
+    }
+
+    /// Whether players respawn after dying in the current phase
+    pub fn allows_respawn(&self, config: &MatchConfig) -> bool {
+        !(config.elimination && self.phase == MatchPhase::InProgress)
+    }
+
+    /// The contender with strictly the most kills this round
+    pub fn round_leader(&self) -> Option<Contender> {
+        leader(&self.round_kills)
+    }
+
+    /// The contender that won enough rounds to win the match
+    pub fn match_winner(&self, config: &MatchConfig) -> Option<Contender> {
+        self.round_wins
+            .iter()
+            .find(|(_, wins)| **wins >= config.rounds_to_win)
+            .map(|(contender, _)| *contender)
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.timer.remaining()
+    }
+
+    /// The state as it is sent to the clients
+    pub fn event(&self) -> MatchStateEvent {
+        MatchStateEvent {
+            phase: self.phase,
+            round: self.round,
+            remaining: self.remaining().as_secs_f32(),
+            round_wins: self
+                .round_wins
+                .iter()
+                .map(|(contender, wins)| (*contender, *wins))
+                .collect(),
+        }
+    }
+
+    /// Mirrors the state received from the server
+    pub fn apply(&mut self, event: &MatchStateEvent) {
+        self.phase = event.phase;
+        self.round = event.round;
+        self.timer = Timer::from_seconds(event.remaining, TimerMode::Once);
+        self.round_wins = event.round_wins.iter().copied().collect();
+    }
+}
+
+fn leader(counts: &HashMap<Contender, u32>) -> Option<Contender> {
+    let most = counts.values().copied().max()?;
+    let mut leaders = counts.iter().filter(|(_, count)| **count == most);
+
+    match (leaders.next(), leaders.next()) {
+        (Some((contender, _)), None) => Some(*contender),
+        _ => None,
+    }
+}
+
+/// Seeds the randomness of the current match on the server,
+/// drawn again whenever a new match begins
+#[derive(Debug, Clone, Copy, Resource, Deref)]
+pub struct MatchSeed(pub u64);
+
+impl MatchSeed {
+    /// A seed drawn from the clock
+    pub fn draw() -> Self {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+
+        Self(hash_bytes(&now.as_nanos().to_le_bytes()))
+    }
+
+    /// The spray seed of the equipment in a player's inventory slot,
+    /// so no two weapons fire the same pattern
+    pub fn spray_seed(&self, network_id: NetworkId, slot: usize) -> u64 {
+        extend_hash(self.0 ^ *network_id, &(slot as u64).to_le_bytes())
+    }
+}
+
+impl Default for MatchSeed {
+    fn default() -> Self {
+        Self::draw()
+    }
+}
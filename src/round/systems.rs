@@ -0,0 +1,230 @@
+use std::collections::HashSet;
+
+use bevy::{
+    ecs::{
+        entity::Entity,
+        event::{EventReader, EventWriter},
+        query::{Has, With, Without},
+        system::{Commands, Query, Res, ResMut},
+    },
+    time::{Time, Timer, TimerMode},
+};
+use bevy_renet::renet::ServerEvent;
+
+use crate::{
+    asset::resources::{AssetsConfig, MatchConfig},
+    deck::keyword::{
+        components::Projectile,
+        events::{DespawnProjectileEvent, EntityDamagedEvent},
+    },
+    networking::{components::NetworkId, networking::ServerMessages},
+    player::components::{Death, Player, Respawn, Team},
+    score::resources::Scoreboard,
+    server::{events::ClientConnectedEvent, params::ServerMessenger, resources::ServerLobby},
+};
+
+use super::{
+    events::{MatchStateEvent, ResetWorldEvent},
+    resources::{Contender, MatchPhase, MatchSeed, MatchState},
+};
+
+/// Moves the match through its phases and announces every change to the clients
+pub fn match_phase_system(
+    time: Res<Time>,
+    asset_config: Res<AssetsConfig>,
+    mut match_state: ResMut<MatchState>,
+    mut writer_reset_world: EventWriter<ResetWorldEvent>,
+    mut server_messenger: ServerMessenger,
+    team_query: Query<&Team>,
+    contender_query: Query<(&Player, &Team, Has<Death>), Without<Respawn>>,
+) {
+    let config = &asset_config.match_rules;
+    match_state.timer.tick(time.delta());
+
+    // spectators don't count towards starting a match
+    let players = team_query.iter().filter(|team| team.is_playing()).count();
+    let enough_players = players >= config.min_players.max(1);
+
+    let next = match match_state.phase {
+        MatchPhase::Warmup => {
+            // the warmup only counts down once enough players are in
+            if !enough_players {
+                match_state.timer.reset();
+            }
+
+            match_state
+                .timer
+                .finished()
+                .then_some(MatchPhase::InProgress)
+        }
+        MatchPhase::InProgress if !enough_players => Some(MatchPhase::Warmup),
+        MatchPhase::InProgress => round_result(
+            &match_state,
+            config,
+            contender_query.iter().filter_map(|(player, team, dead)| {
+                Contender::of(*team, player.id.raw()).map(|contender| (contender, dead))
+            }),
+        )
+        .map(|winner| MatchPhase::RoundEnd { winner }),
+        MatchPhase::RoundEnd { .. } => {
+            match_state
+                .timer
+                .finished()
+                .then(|| match match_state.match_winner(config) {
+                    Some(winner) => MatchPhase::MatchEnd {
+                        winner: Some(winner),
+                    },
+                    None => MatchPhase::InProgress,
+                })
+        }
+        MatchPhase::MatchEnd { .. } => match_state.timer.finished().then_some(MatchPhase::Warmup),
+    };
+
+    let Some(next) = next else {
+        return;
+    };
+
+    let new_match = next == MatchPhase::InProgress && match_state.round == 0;
+    match_state.enter(next, config);
+
+    if matches!(next, MatchPhase::Warmup | MatchPhase::InProgress) {
+        writer_reset_world.send(ResetWorldEvent { new_match });
+    }
+
+    server_messenger.broadcast(ServerMessages::MatchState(match_state.event()));
+}
+
+/// The winner once the round is decided, `None` on a draw
+fn round_result(
+    match_state: &MatchState,
+    config: &MatchConfig,
+    contenders: impl Iterator<Item = (Contender, bool)>,
+) -> Option<Option<Contender>> {
+    if config.score_limit > 0 {
+        let winner = match_state
+            .round_kills
+            .iter()
+            .find(|(_, kills)| **kills >= config.score_limit);
+
+        if let Some((contender, _)) = winner {
+            return Some(Some(*contender));
+        }
+    }
+
+    if config.elimination {
+        let mut competing = HashSet::new();
+        let mut standing = HashSet::new();
+
+        // players waiting on a respawn at the start of the round are left out
+        for (contender, dead) in contenders {
+            competing.insert(contender);
+            if !dead {
+                standing.insert(contender);
+            }
+        }
+
+        if competing.len() > 1 && standing.len() <= 1 {
+            return Some(standing.into_iter().next());
+        }
+    }
+
+    match_state
+        .timer
+        .finished()
+        .then(|| match_state.round_leader())
+}
+
+/// Counts the kills of each contender towards the round's score limit,
+/// kills during warmup or between rounds and team kills don't count
+pub fn count_round_kills(
+    mut reader_entity_damaged: EventReader<EntityDamagedEvent>,
+    mut match_state: ResMut<MatchState>,
+    lobby: Res<ServerLobby>,
+    team_query: Query<&Team>,
+) {
+    for entity_damaged in reader_entity_damaged.read() {
+        if !entity_damaged.lethal || match_state.phase != MatchPhase::InProgress {
+            continue;
+        }
+
+        let Some(killer) = entity_damaged.attacker else {
+            continue;
+        };
+        let Some(killer_team) = lobby
+            .players
+            .get(&killer.raw())
+            .and_then(|killer| team_query.get(*killer).ok())
+        else {
+            continue;
+        };
+
+        if let (Team::Index(killer_index), Ok(Team::Index(victim_index))) =
+            (killer_team, team_query.get(entity_damaged.target))
+        {
+            if killer_index == victim_index {
+                continue;
+            }
+        }
+
+        if let Some(contender) = Contender::of(*killer_team, killer.raw()) {
+            *match_state.round_kills.entry(contender).or_default() += 1;
+        }
+    }
+}
+
+/// Respawns every player and clears the projectiles in flight,
+/// the respawn itself happens through the players' respawn timers
+pub fn reset_world_system(
+    mut commands: Commands,
+    mut reader_reset_world: EventReader<ResetWorldEvent>,
+    mut writer_despawn_projectile: EventWriter<DespawnProjectileEvent>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut match_seed: ResMut<MatchSeed>,
+    player_query: Query<Entity, With<Player>>,
+    projectile_query: Query<&NetworkId, With<Projectile>>,
+) {
+    for reset_world in reader_reset_world.read() {
+        for entity in &player_query {
+            commands
+                .entity(entity)
+                .insert(Respawn(Timer::from_seconds(0.0, TimerMode::Once)));
+        }
+
+        for network_id in &projectile_query {
+            writer_despawn_projectile.send(DespawnProjectileEvent {
+                network_id: *network_id,
+            });
+        }
+
+        if reset_world.new_match {
+            scoreboard.reset();
+            *match_seed = MatchSeed::draw();
+        }
+    }
+}
+
+/// Tells connecting clients what state the match is in
+pub fn send_match_state(
+    mut reader_client_connected: EventReader<ClientConnectedEvent>,
+    mut server_messenger: ServerMessenger,
+    match_state: Res<MatchState>,
+) {
+    for client_connected in reader_client_connected.read() {
+        if let ServerEvent::ClientConnected { client_id } = client_connected.0 {
+            server_messenger.send(client_id, ServerMessages::MatchState(match_state.event()));
+        }
+    }
+}
+
+pub fn on_match_state(
+    mut reader_match_state: EventReader<MatchStateEvent>,
+    mut match_state: ResMut<MatchState>,
+) {
+    for match_state_event in reader_match_state.read() {
+        match_state.apply(match_state_event);
+    }
+}
+
+pub fn tick_match_timer(time: Res<Time>, mut match_state: ResMut<MatchState>) {
+    match_state.timer.tick(time.delta());
+}
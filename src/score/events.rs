@@ -0,0 +1,41 @@
+use bevy::prelude::Event;
+use serde::{Deserialize, Serialize};
+
+use crate::client::resources::ClientId;
+
+use super::resources::PlayerScore;
+
+/**
+ * Kill Feed Event
+ *
+ * A Bevy Event to inform client systems
+ * a player has been killed, contains the corresponding
+ * server message
+ */
+#[derive(Event, Debug, Clone, Serialize, Deserialize)]
+pub struct KillFeedEvent {
+    /// `None` when the victim was not killed by a player
+    pub killer: Option<ClientId>,
+    pub victim: ClientId,
+    pub assisters: Vec<ClientId>,
+}
+
+/**
+ * Scoreboard Event
+ *
+ * A Bevy Event to inform client systems
+ * of the current scoreboard, contains the corresponding
+ * server message
+ */
+#[derive(Event, Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreboardEvent {
+    pub entries: Vec<ScoreboardEntry>,
+}
+
+/// A player's row on the scoreboard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreboardEntry {
+    pub id: ClientId,
+    pub name: String,
+    pub score: PlayerScore,
+}
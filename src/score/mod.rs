@@ -0,0 +1,61 @@
+use bevy::prelude::*;
+
+use crate::{
+    client::sets::Connected,
+    enums::GameState,
+    networking::{is_client, is_server, sets::SimulationSet},
+};
+
+use self::{
+    events::{KillFeedEvent, ScoreboardEvent},
+    resources::{ClientScoreboard, KillFeed, Scoreboard, ScoreboardSync},
+    systems::{
+        on_kill_feed, on_scoreboard, score_damage_system, scoreboard_connection_system,
+        scoreboard_sync_system, tick_kill_feed,
+    },
+};
+
+pub mod events;
+pub mod resources;
+mod systems;
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            score_damage_system
+                .in_set(SimulationSet::Sync)
+                .run_if(is_server())
+                .run_if(in_state(GameState::Gameloop)),
+        );
+        app.add_systems(
+            Update,
+            (scoreboard_connection_system, scoreboard_sync_system)
+                .chain()
+                .run_if(is_server())
+                .run_if(in_state(GameState::Gameloop)),
+        );
+
+        app.add_systems(
+            Update,
+            (
+                on_scoreboard,
+                on_kill_feed.after(on_scoreboard),
+                tick_kill_feed,
+            )
+                .run_if(is_client())
+                .run_if(in_state(GameState::Gameloop))
+                .in_set(Connected),
+        );
+
+        app.add_event::<KillFeedEvent>();
+        app.add_event::<ScoreboardEvent>();
+
+        app.insert_resource(Scoreboard::default());
+        app.insert_resource(ScoreboardSync::default());
+        app.insert_resource(ClientScoreboard::default());
+        app.insert_resource(KillFeed::default());
+    }
+}
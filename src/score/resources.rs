@@ -0,0 +1,166 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use bevy::{
+    prelude::Resource,
+    time::{Timer, TimerMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::client::resources::ClientId;
+
+use super::events::ScoreboardEntry;
+
+/// Kill, death, assist and damage stats of a player
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlayerScore {
+    pub kills: u32,
+    pub deaths: u32,
+    pub assists: u32,
+    pub damage_dealt: f32,
+}
+
+/// The stats of the connected players, kept by the server
+#[derive(Debug, Default, Resource)]
+pub struct Scoreboard {
+    pub players: HashMap<u64, PlayerScore>,
+    /// The clients that damaged each player since the player last died
+    pub damagers: HashMap<u64, HashSet<u64>>,
+    /// Whether the scores changed since they were last synced
+    pub changed: bool,
+}
+
+impl Scoreboard {
+    pub fn join(&mut self, client_id: u64) {
+        self.players.entry(client_id).or_default();
+        self.changed = true;
+    }
+
+    /// Clears every player's stats for a new match
+    pub fn reset(&mut self) {
+        for score in self.players.values_mut() {
+            *score = PlayerScore::default();
+        }
+        self.damagers.clear();
+        self.changed = true;
+    }
+
+    pub fn leave(&mut self, client_id: u64) {
+        self.players.remove(&client_id);
+        self.damagers.remove(&client_id);
+        for damagers in self.damagers.values_mut() {
+            damagers.remove(&client_id);
+        }
+        self.changed = true;
+    }
+
+    /// Credits the attacker with damage dealt to the victim, self damage is not credited
+    pub fn record_damage(&mut self, attacker: u64, victim: u64, damage: f32) {
+        if attacker == victim {
+            return;
+        }
+
+        if let Some(score) = self.players.get_mut(&attacker) {
+            score.damage_dealt += damage;
+            self.damagers.entry(victim).or_default().insert(attacker);
+            self.changed = true;
+        }
+    }
+
+    /// Records the victim's death and credits the kill,
+    /// returns the other players that damaged the victim, credited with assists
+    pub fn record_kill(&mut self, killer: Option<u64>, victim: u64) -> Vec<u64> {
+        let killer = killer.filter(|killer| *killer != victim);
+
+        if let Some(score) = self.players.get_mut(&victim) {
+            score.deaths += 1;
+        }
+
+        if let Some(score) = killer.and_then(|killer| self.players.get_mut(&killer)) {
+            score.kills += 1;
+        }
+
+        let mut assisters: Vec<u64> = self
+            .damagers
+            .remove(&victim)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|damager| Some(*damager) != killer)
+            .collect();
+        assisters.sort_unstable();
+
+        for assister in assisters.iter() {
+            if let Some(score) = self.players.get_mut(assister) {
+                score.assists += 1;
+            }
+        }
+
+        self.changed = true;
+
+        assisters
+    }
+}
+
+/// How often the server sends the scoreboard to the clients, when it changed
+#[derive(Debug, Resource)]
+pub struct ScoreboardSync(pub Timer);
+
+impl Default for ScoreboardSync {
+    fn default() -> Self {
+        Self(Timer::from_seconds(1.0, TimerMode::Repeating))
+    }
+}
+
+/// The scoreboard last received from the server
+#[derive(Debug, Default, Resource)]
+pub struct ClientScoreboard {
+    pub entries: Vec<ScoreboardEntry>,
+}
+
+impl ClientScoreboard {
+    pub fn name(&self, id: ClientId) -> String {
+        self.entries
+            .iter()
+            .find(|entry| entry.id == id)
+            .map(|entry| entry.name.clone())
+            .unwrap_or_else(|| format!("Player {}", *id))
+    }
+}
+
+/// A line of the kill feed and how long it stays on screen
+#[derive(Debug)]
+pub struct KillFeedLine {
+    pub text: String,
+    pub timer: Timer,
+}
+
+/// The latest kills, shown on the client
+#[derive(Debug, Resource)]
+pub struct KillFeed {
+    pub lines: VecDeque<KillFeedLine>,
+    pub capacity: usize,
+    /// How long, in seconds, a kill stays in the feed
+    pub duration: f32,
+}
+
+impl Default for KillFeed {
+    fn default() -> Self {
+        Self {
+            lines: VecDeque::new(),
+            capacity: 5,
+            duration: 6.0,
+        }
+    }
+}
+
+impl KillFeed {
+    pub fn push(&mut self, text: String) {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+
+        self.lines.push_back(KillFeedLine {
+            text,
+            timer: Timer::from_seconds(self.duration, TimerMode::Once),
+        });
+    }
+}
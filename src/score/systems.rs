@@ -0,0 +1,158 @@
+use bevy::{
+    ecs::{
+        event::EventReader,
+        system::{Query, Res, ResMut},
+    },
+    time::Time,
+};
+use bevy_renet::renet::ServerEvent;
+
+use crate::{
+    client::resources::ClientId,
+    deck::keyword::events::EntityDamagedEvent,
+    networking::networking::ServerMessages,
+    player::components::Player,
+    server::{
+        events::{ClientConnectedEvent, ClientDisconnectedEvent},
+        params::ServerMessenger,
+        resources::ServerLobby,
+    },
+};
+
+use super::{
+    events::{KillFeedEvent, ScoreboardEntry, ScoreboardEvent},
+    resources::{ClientScoreboard, KillFeed, Scoreboard, ScoreboardSync},
+};
+
+/// Adds connecting players to the scoreboard and removes the ones leaving
+pub fn scoreboard_connection_system(
+    mut reader_client_connected: EventReader<ClientConnectedEvent>,
+    mut reader_client_disconnected: EventReader<ClientDisconnectedEvent>,
+    mut scoreboard: ResMut<Scoreboard>,
+) {
+    for client_connected in reader_client_connected.read() {
+        if let ServerEvent::ClientConnected { client_id } = client_connected.0 {
+            scoreboard.join(client_id.raw());
+        }
+    }
+
+    for client_disconnected in reader_client_disconnected.read() {
+        if let ServerEvent::ClientDisconnected { client_id, .. } = client_disconnected.0 {
+            scoreboard.leave(client_id.raw());
+        }
+    }
+}
+
+/// Credits the damage players take to the attacker and announces kills in the kill feed
+pub fn score_damage_system(
+    mut reader_entity_damaged: EventReader<EntityDamagedEvent>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut server_messenger: ServerMessenger,
+    player_query: Query<&Player>,
+) {
+    for entity_damaged in reader_entity_damaged.read() {
+        let Ok(victim) = player_query.get(entity_damaged.target) else {
+            continue;
+        };
+        let victim = victim.id.raw();
+        let attacker = entity_damaged.attacker.map(|attacker| attacker.raw());
+
+        if let Some(attacker) = attacker {
+            scoreboard.record_damage(attacker, victim, entity_damaged.damage);
+        }
+
+        if !entity_damaged.lethal {
+            continue;
+        }
+
+        let killer = attacker.filter(|attacker| *attacker != victim);
+        let assisters = scoreboard.record_kill(killer, victim);
+
+        server_messenger.broadcast(ServerMessages::KillFeed(KillFeedEvent {
+            killer: killer.map(ClientId),
+            victim: ClientId(victim),
+            assisters: assisters.into_iter().map(ClientId).collect(),
+        }));
+    }
+}
+
+/// Periodically sends the scoreboard to the clients when it changed
+pub fn scoreboard_sync_system(
+    time: Res<Time>,
+    lobby: Res<ServerLobby>,
+    mut scoreboard_sync: ResMut<ScoreboardSync>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut server_messenger: ServerMessenger,
+) {
+    if !scoreboard_sync.0.tick(time.delta()).just_finished() || !scoreboard.changed {
+        return;
+    }
+
+    let entries = scoreboard
+        .players
+        .iter()
+        .map(|(client_id, score)| ScoreboardEntry {
+            id: ClientId(*client_id),
+            name: lobby
+                .names
+                .get(client_id)
+                .cloned()
+                .unwrap_or_else(|| format!("Player {}", client_id)),
+            score: *score,
+        })
+        .collect();
+
+    scoreboard.changed = false;
+    server_messenger.broadcast(ServerMessages::Scoreboard(ScoreboardEvent { entries }));
+}
+
+pub fn on_scoreboard(
+    mut reader_scoreboard: EventReader<ScoreboardEvent>,
+    mut client_scoreboard: ResMut<ClientScoreboard>,
+) {
+    if let Some(scoreboard) = reader_scoreboard.read().last() {
+        let mut entries = scoreboard.entries.clone();
+        entries.sort_by(|a, b| {
+            b.score
+                .kills
+                .cmp(&a.score.kills)
+                .then(a.score.deaths.cmp(&b.score.deaths))
+                .then(a.name.cmp(&b.name))
+        });
+
+        client_scoreboard.entries = entries;
+    }
+}
+
+pub fn on_kill_feed(
+    mut reader_kill_feed: EventReader<KillFeedEvent>,
+    mut kill_feed: ResMut<KillFeed>,
+    client_scoreboard: Res<ClientScoreboard>,
+) {
+    for kill in reader_kill_feed.read() {
+        let victim = client_scoreboard.name(kill.victim);
+
+        let text = match kill.killer {
+            Some(killer) => {
+                let mut killers = vec![client_scoreboard.name(killer)];
+                killers.extend(
+                    kill.assisters
+                        .iter()
+                        .map(|assister| client_scoreboard.name(*assister)),
+                );
+                format!("{} killed {}", killers.join(" + "), victim)
+            }
+            None => format!("{} died", victim),
+        };
+
+        kill_feed.push(text);
+    }
+}
+
+pub fn tick_kill_feed(time: Res<Time>, mut kill_feed: ResMut<KillFeed>) {
+    for line in kill_feed.lines.iter_mut() {
+        line.timer.tick(time.delta());
+    }
+
+    kill_feed.lines.retain(|line| !line.timer.finished());
+}
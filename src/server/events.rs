@@ -3,6 +3,8 @@ use bevy_renet::renet::ServerEvent;
 
 use crate::{input::resources::PlayerInput, player::events::PlayerCommand};
 
+use super::resources::Violation;
+
 /**
  * Client Connected Event
  *
@@ -39,6 +41,15 @@ pub struct ClientSentInputEvent(pub PlayerInput, pub u64);
 #[derive(Event, Debug)]
 pub struct ClientSentCommandEvent(pub PlayerCommand, pub u64);
 
+/**
+ * Client Violation Event
+ *
+ * A Bevy Event to report suspicious behavior of a client,
+ * scored on the server lobby to warn or kick the client.
+ */
+#[derive(Event, Debug)]
+pub struct ClientViolationEvent(pub Violation, pub u64);
+
 #[derive(Event, Debug)]
 pub struct SyncEntityEvent {
     pub entity: Entity,
@@ -5,44 +5,64 @@ use std::{
 
 use bevy::prelude::*;
 use bevy_renet::renet::{
-    transport::{NetcodeServerTransport, ServerAuthentication, ServerConfig},
+    transport::{NetcodeServerTransport, ServerConfig},
     RenetServer,
 };
 
 use crate::{
     enums::GameState,
-    networking::config::{connection_config, PROTOCOL_ID},
+    networking::{config::connection_config, resources::NetworkRegistry, sets::SimulationSet},
+    settings::SettingsError,
 };
 
 use self::{
     events::{
         ClientConnectedEvent, ClientDisconnectedEvent, ClientSentCommandEvent,
-        ClientSentInputEvent, SyncEntityEvent,
+        ClientSentInputEvent, ClientViolationEvent, SyncEntityEvent,
     },
-    resources::ServerLobby,
+    resources::{
+        ClientRateLimits, LagCompensation, NetworkStats, PendingClients, ServerLobby, ServerTick,
+        SnapshotBaselines, ViolationPolicy,
+    },
+    settings::ServerSettings,
     systems::{
-        client_connected_to_server, client_disconnected, on_demand_server_network_sync,
-        server_network_sync, server_update_system,
+        advance_server_tick, client_connected_to_server, client_disconnected,
+        enforce_violations_system, record_hitbox_history, register_player_name,
+        server_handshake_system, server_network_sync, server_receive_messages_system,
+        server_update_system,
     },
 };
 
 pub mod events;
+pub mod params;
 pub mod resources;
+pub mod settings;
 mod systems;
+pub mod token;
 
 pub struct ServerPlugin;
 
 impl Plugin for ServerPlugin {
     fn build(&self, app: &mut App) {
-        host_server(app);
+        app.add_systems(
+            FixedUpdate,
+            (
+                advance_server_tick.in_set(SimulationSet::Tick),
+                record_hitbox_history.in_set(SimulationSet::Sync),
+                server_network_sync.in_set(SimulationSet::Sync),
+            )
+                .run_if(in_state(GameState::Gameloop)),
+        );
         app.add_systems(
             Update,
             (
-                on_demand_server_network_sync,
+                register_player_name.before(client_connected_to_server),
                 client_connected_to_server,
                 client_disconnected,
-                server_network_sync,
                 server_update_system,
+                server_handshake_system.after(server_update_system),
+                server_receive_messages_system,
+                enforce_violations_system,
             )
                 .run_if(in_state(GameState::Gameloop)),
         );
@@ -51,29 +71,44 @@ impl Plugin for ServerPlugin {
         app.add_event::<ClientDisconnectedEvent>();
         app.add_event::<ClientSentInputEvent>();
         app.add_event::<ClientSentCommandEvent>();
+        app.add_event::<ClientViolationEvent>();
         app.add_event::<SyncEntityEvent>();
 
         app.insert_resource(ServerLobby::default());
+        app.insert_resource(ServerTick::default());
+        app.insert_resource(SnapshotBaselines::default());
+        app.insert_resource(NetworkStats::default());
+        app.insert_resource(ClientRateLimits::default());
+        app.insert_resource(ViolationPolicy::default());
+        app.insert_resource(LagCompensation::default());
+        app.insert_resource(PendingClients::default());
+        app.insert_resource(NetworkRegistry::default());
     }
 }
 
-fn host_server(app: &mut App) {
+/// Binds the server socket and adds the server and transport resources,
+/// hosted with the given settings
+pub fn host_server(app: &mut App, settings: &ServerSettings) -> Result<(), SettingsError> {
     let server = RenetServer::new(connection_config());
 
-    let public_addr = "0.0.0.0:5000".parse().unwrap();
-    let socket = UdpSocket::bind(public_addr).unwrap();
+    let socket = UdpSocket::bind(settings.bind_addr)
+        .map_err(|error| SettingsError::Bind(settings.bind_addr, error))?;
     let current_time: Duration = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap();
+        .map_err(|_| SettingsError::Clock)?;
     let server_config = ServerConfig {
-        max_clients: 64,
-        protocol_id: PROTOCOL_ID,
+        max_clients: settings.max_clients,
+        protocol_id: settings.protocol_id,
         current_time,
-        public_addresses: vec![std::net::SocketAddr::V4(public_addr)],
-        authentication: ServerAuthentication::Unsecure,
+        public_addresses: vec![settings.public_addr],
+        authentication: settings.server_authentication()?,
     };
 
-    let transport = NetcodeServerTransport::new(server_config, socket).unwrap();
+    let transport = NetcodeServerTransport::new(server_config, socket)
+        .map_err(|error| SettingsError::Bind(settings.bind_addr, error))?;
     app.insert_resource(server);
     app.insert_resource(transport);
+    app.insert_resource(settings.clone());
+
+    Ok(())
 }
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use bevy::ecs::system::{Res, ResMut, SystemParam};
+use bevy_renet::renet::{ClientId, RenetServer};
+
+use crate::networking::{
+    channels::{ClientChannel, ServerChannel},
+    codec,
+    networking::{ServerMessageEnvelope, ServerMessages},
+    resources::TickRate,
+};
+
+use super::resources::{ClientRateLimits, PendingClients, ServerTick};
+
+/// Sends server messages to clients, stamped with the current server tick
+#[derive(SystemParam)]
+pub struct ServerMessenger<'w> {
+    server: ResMut<'w, RenetServer>,
+    server_tick: Res<'w, ServerTick>,
+    pending_clients: Res<'w, PendingClients>,
+}
+
+impl ServerMessenger<'_> {
+    pub fn send(&mut self, client_id: ClientId, message: ServerMessages) {
+        let message = self.serialize(message);
+        self.server
+            .send_message(client_id, ServerChannel::ServerMessages, message);
+    }
+
+    /// Sends a message to every client that joined the game,
+    /// clients still in their handshake are sent the full state once they join
+    pub fn broadcast(&mut self, message: ServerMessages) {
+        let message = self.serialize(message);
+        for client_id in self.server.clients_id() {
+            if self.pending_clients.clients.contains_key(&client_id.raw()) {
+                continue;
+            }
+
+            self.server
+                .send_message(client_id, ServerChannel::ServerMessages, message.clone());
+        }
+    }
+
+    /// Disconnects a client, for clients the server won't keep talking to
+    pub fn disconnect(&mut self, client_id: ClientId) {
+        self.server.disconnect(client_id);
+    }
+
+    fn serialize(&self, message: ServerMessages) -> Vec<u8> {
+        codec::encode(&ServerMessageEnvelope::new(**self.server_tick, &message))
+    }
+}
+
+/// Rate limits the messages of clients, by the rate the server ticks at
+#[derive(SystemParam)]
+pub struct RateLimiter<'w> {
+    rate_limits: ResMut<'w, ClientRateLimits>,
+    tick_rate: Res<'w, TickRate>,
+}
+
+impl RateLimiter<'_> {
+    /// Whether the client may send another message on the channel
+    pub fn allow(&mut self, client_id: u64, channel: ClientChannel, now: Duration) -> bool {
+        self.rate_limits
+            .allow(client_id, channel, **self.tick_rate, now)
+    }
+
+    pub fn tick_rate(&self) -> f64 {
+        **self.tick_rate
+    }
+}
@@ -1,8 +1,464 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    time::Duration,
+};
 
-use bevy::prelude::{Entity, Resource};
+use bevy::prelude::{Deref, Entity, Resource, Vec2};
+
+use crate::networking::{
+    channels::{ClientChannel, RateLimit},
+    components::NetworkId,
+    models::{EntityDelta, NetworkedEntities, QuantizedEntity},
+};
 
 #[derive(Debug, Default, Resource)]
 pub struct ServerLobby {
     pub players: HashMap<u64, Entity>,
+    /// Display names of the connected clients
+    pub names: HashMap<u64, String>,
+    /// Violation scores of the connected clients
+    pub violations: HashMap<u64, ViolationScore>,
+}
+
+impl ServerLobby {
+    /// Adds a violation to the client's score and decides what is done about the client,
+    /// the score decays by the time passed since the client's previous violation
+    pub fn report_violation(
+        &mut self,
+        client_id: u64,
+        violation: Violation,
+        policy: &ViolationPolicy,
+        now: Duration,
+    ) -> ViolationAction {
+        let violations = self.violations.entry(client_id).or_default();
+
+        let idle = now.saturating_sub(violations.last_violation).as_secs_f32();
+        violations.score = (violations.score - idle * policy.decay_per_second).max(0.0);
+        if violations.score < policy.warn_at {
+            violations.warned = false;
+        }
+
+        violations.score += violation.weight();
+        violations.total += 1;
+        violations.last_violation = now;
+
+        if violations.score >= policy.kick_at {
+            ViolationAction::Kick
+        } else if violations.score >= policy.warn_at && !violations.warned {
+            violations.warned = true;
+            ViolationAction::Warn
+        } else {
+            ViolationAction::None
+        }
+    }
+}
+
+/// The server's simulation tick, increases by one every fixed timestep
+#[derive(Debug, Default, Resource, Deref)]
+pub struct ServerTick(pub u64);
+
+/// Snapshots sent to a client, kept so later snapshots can be
+/// sent as deltas against the one the client acknowledged
+#[derive(Debug, Default)]
+pub struct ClientBaseline {
+    pub acked: Option<u64>,
+    pub sent: VecDeque<(u64, HashMap<NetworkId, QuantizedEntity>)>,
+}
+
+impl ClientBaseline {
+    /// How many sent snapshots are kept waiting for an acknowledgement
+    pub const CAPACITY: usize = 64;
+
+    pub fn acknowledge(&mut self, tick: u64) {
+        if self.acked.is_some_and(|acked| acked >= tick) {
+            return;
+        }
+
+        self.acked = Some(tick);
+        while self.sent.front().is_some_and(|(sent, _)| *sent < tick) {
+            self.sent.pop_front();
+        }
+    }
+
+    /// The acknowledged snapshot, if it has not been dropped
+    pub fn baseline(&self) -> Option<&(u64, HashMap<NetworkId, QuantizedEntity>)> {
+        let acked = self.acked?;
+        self.sent.iter().find(|(tick, _)| *tick == acked)
+    }
+
+    /// The snapshot of the entities for the client, as deltas against its
+    /// baseline, `forced` entities are sent in full regardless of it
+    pub fn snapshot(
+        &self,
+        tick: u64,
+        entities: &HashMap<NetworkId, QuantizedEntity>,
+        forced: &HashSet<NetworkId>,
+    ) -> NetworkedEntities {
+        let baseline = self.baseline();
+
+        let mut networked_entities = NetworkedEntities {
+            tick,
+            baseline: baseline.map(|(tick, _)| *tick),
+            ..Default::default()
+        };
+
+        for (network_id, entity) in entities.iter() {
+            let previous = baseline
+                .filter(|_| !forced.contains(network_id))
+                .and_then(|(_, baseline_entities)| baseline_entities.get(network_id));
+
+            if let Some(delta) = EntityDelta::between(*network_id, previous, entity) {
+                networked_entities.entities.push(delta);
+            }
+        }
+
+        if let Some((_, baseline_entities)) = baseline {
+            networked_entities.removed = baseline_entities
+                .keys()
+                .filter(|network_id| !entities.contains_key(network_id))
+                .copied()
+                .collect();
+        }
+
+        networked_entities
+    }
+
+    pub fn record(&mut self, tick: u64, entities: HashMap<NetworkId, QuantizedEntity>) {
+        if self.sent.len() == Self::CAPACITY {
+            self.sent.pop_front();
+        }
+
+        self.sent.push_back((tick, entities));
+    }
+}
+
+/// The snapshot baselines of every connected client
+#[derive(Debug, Default, Resource)]
+pub struct SnapshotBaselines {
+    pub clients: HashMap<u64, ClientBaseline>,
+}
+
+/// Snapshot data sent to a single client
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ClientBandwidth {
+    pub bytes_sent: u64,
+    pub snapshots_sent: u64,
+}
+
+impl ClientBandwidth {
+    pub fn average_snapshot_size(&self) -> f64 {
+        if self.snapshots_sent == 0 {
+            return 0.0;
+        }
+
+        self.bytes_sent as f64 / self.snapshots_sent as f64
+    }
+
+    /// Bytes per second when a snapshot is sent every tick
+    pub fn bytes_per_second(&self, tick_rate: f64) -> f64 {
+        self.average_snapshot_size() * tick_rate
+    }
+}
+
+/// Snapshot bandwidth used by each client
+#[derive(Debug, Default, Resource)]
+pub struct NetworkStats {
+    pub clients: HashMap<u64, ClientBandwidth>,
+}
+
+impl NetworkStats {
+    pub fn record(&mut self, client_id: u64, bytes: usize) {
+        let bandwidth = self.clients.entry(client_id).or_default();
+        bandwidth.bytes_sent += bytes as u64;
+        bandwidth.snapshots_sent += 1;
+    }
+}
+
+/// Suspicious behavior a client was caught at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Violation {
+    /// A message that failed to decode
+    Malformed(ClientChannel),
+    /// A message past the channel's rate limit
+    RateLimited(ClientChannel),
+    /// An aim that is not finite or too far from the player
+    InvalidAim,
+}
+
+impl Violation {
+    /// How much the violation adds to the client's score
+    pub fn weight(&self) -> f32 {
+        match self {
+            Violation::Malformed(_) => 2.0,
+            Violation::RateLimited(_) => 0.5,
+            Violation::InvalidAim => 5.0,
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Malformed(channel) => write!(f, "malformed message on {:?}", channel),
+            Violation::RateLimited(channel) => write!(f, "too many messages on {:?}", channel),
+            Violation::InvalidAim => write!(f, "invalid aim"),
+        }
+    }
+}
+
+/// What is done about a client after a violation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViolationAction {
+    None,
+    Warn,
+    Kick,
+}
+
+/// The violation score of a single client, decaying over time
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ViolationScore {
+    pub score: f32,
+    pub total: u64,
+    pub last_violation: Duration,
+    /// Whether the client was warned since its score last dropped below the warn threshold
+    pub warned: bool,
+}
+
+/// Thresholds suspicious clients are judged by
+#[derive(Debug, Resource)]
+pub struct ViolationPolicy {
+    /// Score at which the client is warned
+    pub warn_at: f32,
+    /// Score at which the client is kicked
+    pub kick_at: f32,
+    /// Score forgiven per second without violations
+    pub decay_per_second: f32,
+    /// How far from the player an aim may be
+    pub max_aim_distance: f32,
+}
+
+impl Default for ViolationPolicy {
+    fn default() -> Self {
+        Self {
+            warn_at: 10.0,
+            kick_at: 25.0,
+            decay_per_second: 1.0,
+            max_aim_distance: 2048.0,
+        }
+    }
+}
+
+impl ViolationPolicy {
+    /// Whether an aim is finite and within reach of the player at `origin`
+    pub fn is_valid_aim(&self, aim: Vec2, origin: Option<Vec2>) -> bool {
+        aim.is_finite()
+            && !origin.is_some_and(|origin| aim.distance(origin) > self.max_aim_distance)
+    }
+}
+
+/// A token bucket of messages a client may send on a channel
+#[derive(Debug, Clone, Copy)]
+pub struct TokenBucket {
+    pub tokens: f64,
+    pub last_refill: Duration,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit, now: Duration) -> Self {
+        Self {
+            tokens: limit.burst,
+            last_refill: now,
+        }
+    }
+
+    /// Takes a token, returns false when the bucket is empty
+    pub fn take(&mut self, limit: RateLimit, now: Duration) -> bool {
+        let elapsed = now.saturating_sub(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_second).min(limit.burst);
+        self.last_refill = now;
+
+        if self.tokens < 1.0 {
+            return false;
+        }
+
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+/// The rate limits of each client, per channel
+#[derive(Debug, Default, Resource)]
+pub struct ClientRateLimits {
+    pub clients: HashMap<u64, HashMap<ClientChannel, TokenBucket>>,
+}
+
+impl ClientRateLimits {
+    /// Whether the client may send another message on the channel,
+    /// for a server running at `tick_rate`
+    pub fn allow(
+        &mut self,
+        client_id: u64,
+        channel: ClientChannel,
+        tick_rate: f64,
+        now: Duration,
+    ) -> bool {
+        let limit = channel.rate_limit(tick_rate);
+        self.clients
+            .entry(client_id)
+            .or_default()
+            .entry(channel)
+            .or_insert_with(|| TokenBucket::new(limit, now))
+            .take(limit, now)
+    }
+}
+
+/// A connected client that has not completed the handshake yet
+#[derive(Debug, Clone, Copy)]
+pub struct PendingClient {
+    pub connected_at: Duration,
+    /// When the handshake was rejected, the client is disconnected
+    /// once the rejection had time to reach it
+    pub rejected_at: Option<Duration>,
+}
+
+/// Clients waiting on their handshake, they only join the game once it is accepted
+#[derive(Debug, Resource)]
+pub struct PendingClients {
+    pub clients: HashMap<u64, PendingClient>,
+    /// How long a client may take to send its handshake
+    pub timeout: Duration,
+    /// How long a rejected client stays connected to receive the reason
+    pub rejection_grace: Duration,
+}
+
+impl Default for PendingClients {
+    fn default() -> Self {
+        Self {
+            clients: HashMap::new(),
+            timeout: Duration::from_secs(10),
+            rejection_grace: Duration::from_secs(1),
+        }
+    }
+}
+
+/// How far back hits may be evaluated for lag compensation
+#[derive(Debug, Resource)]
+pub struct LagCompensation {
+    /// The furthest a shot is rewound, shots from clients further
+    /// behind are evaluated this far back instead
+    pub max_rewind: Duration,
+}
+
+impl Default for LagCompensation {
+    fn default() -> Self {
+        Self {
+            max_rewind: Duration::from_millis(250),
+        }
+    }
+}
+
+impl LagCompensation {
+    /// The maximum rewind in ticks of the given length
+    pub fn max_rewind_ticks(&self, timestep: Duration) -> u64 {
+        (self.max_rewind.as_secs_f64() / timestep.as_secs_f64()).ceil() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::{Vec2, Vec3};
+
+    use super::*;
+    use crate::{enums::EntityState, networking::codec};
+
+    fn entities(moved: u64, tick: u64) -> HashMap<NetworkId, QuantizedEntity> {
+        (0..16)
+            .map(|id| {
+                let x = if id == moved {
+                    tick as f32
+                } else {
+                    id as f32 * 10.0
+                };
+                let entity =
+                    QuantizedEntity::new(Vec3::new(x, 5.0, 0.0), Vec2::ZERO, EntityState::Idle);
+
+                (NetworkId(id), entity)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn delta_snapshots_use_less_bandwidth_than_full_ones() {
+        let mut network_stats = NetworkStats::default();
+        // client 1 never acknowledges and is always sent full snapshots
+        let mut full = ClientBaseline::default();
+        let mut delta = ClientBaseline::default();
+
+        for tick in 1..=10 {
+            let entities = entities(0, tick);
+
+            for (client_id, baseline) in [(1, &mut full), (2, &mut delta)] {
+                let snapshot = baseline.snapshot(tick, &entities, &HashSet::new());
+                network_stats.record(client_id, codec::encode(&snapshot).len());
+                baseline.record(tick, entities.clone());
+            }
+            delta.acknowledge(tick);
+        }
+
+        let full = network_stats.clients[&1];
+        let delta = network_stats.clients[&2];
+        assert_eq!(full.snapshots_sent, 10);
+        assert_eq!(delta.snapshots_sent, 10);
+        assert!(delta.bytes_sent < full.bytes_sent);
+        assert!(delta.bytes_per_second(60.0) < full.bytes_per_second(60.0));
+    }
+
+    #[test]
+    fn snapshots_only_carry_changes_and_removals() {
+        let mut baseline = ClientBaseline::default();
+        baseline.record(1, entities(0, 1));
+        baseline.acknowledge(1);
+
+        let mut current = entities(0, 2);
+        current.remove(&NetworkId(5));
+        let forced = HashSet::from([NetworkId(3)]);
+
+        let snapshot = baseline.snapshot(2, &current, &forced);
+        let mut changed: Vec<u64> = snapshot
+            .entities
+            .iter()
+            .map(|delta| delta.network_id.0)
+            .collect();
+        changed.sort();
+
+        assert_eq!(snapshot.baseline, Some(1));
+        assert_eq!(changed, vec![0, 3]);
+        assert_eq!(snapshot.removed, vec![NetworkId(5)]);
+    }
+
+    #[test]
+    fn baselines_acknowledge_and_evict_snapshots() {
+        let mut baseline = ClientBaseline::default();
+        for tick in 1..=5 {
+            baseline.record(tick, HashMap::new());
+        }
+
+        baseline.acknowledge(3);
+        assert_eq!(baseline.acked, Some(3));
+        assert_eq!(baseline.baseline().map(|(tick, _)| *tick), Some(3));
+        // older snapshots can no longer become the baseline
+        assert_eq!(baseline.sent.front().map(|(tick, _)| *tick), Some(3));
+
+        // acknowledgements arriving out of order are ignored
+        baseline.acknowledge(2);
+        assert_eq!(baseline.acked, Some(3));
+
+        // once the acknowledged snapshot is evicted there is no baseline left
+        for tick in 6..6 + ClientBaseline::CAPACITY as u64 {
+            baseline.record(tick, HashMap::new());
+        }
+        assert_eq!(baseline.sent.len(), ClientBaseline::CAPACITY);
+        assert!(baseline.baseline().is_none());
+    }
 }
@@ -0,0 +1,263 @@
+use std::{env, fmt, fs, net::SocketAddr, path::Path, str::FromStr};
+
+use bevy::prelude::Resource;
+use bevy_renet::renet::transport::{ServerAuthentication, NETCODE_KEY_BYTES};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    networking::config::{MAX_TICK_RATE, PROTOCOL_ID, TICK_RATE},
+    settings::{parse, SettingsError},
+};
+
+/// The most clients a netcode server accepts
+const MAX_CLIENTS: usize = 1024;
+
+/// Environment variable the private key can be passed in
+pub const PRIVATE_KEY_VAR: &str = "COBALT_PRIVATE_KEY";
+
+const USAGE: &str = "\
+Usage: server [OPTIONS]
+
+Options:
+  --config <PATH>         Read settings from a JSON file, flags override its values
+  --bind <ADDR>           Address the server socket binds to
+  --public-addr <ADDR>    Address clients connect to
+  --max-clients <N>       Maximum connected clients
+  --tick-rate <HZ>        Simulation ticks per second
+  --protocol-id <ID>      Override the netcode protocol id
+  --asset-config <PATH>   Asset config, relative to the assets folder
+  --auth <MODE>           Authentication mode, unsecure or secure
+  --key-file <PATH>       File with the 32 byte hex key connect tokens are signed with,
+                          required when secure unless the config or COBALT_PRIVATE_KEY sets it
+  -h, --help              Print this message
+
+Commands:
+  token                   Mint a connect token for a player, see server token --help
+  keygen                  Print a new random private key";
+
+/// How connecting clients are authenticated
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthenticationMode {
+    /// Clients pick their own id, for local development
+    #[default]
+    Unsecure,
+    /// Clients must present a connect token signed with the private key
+    Secure,
+}
+
+impl FromStr for AuthenticationMode {
+    type Err = ();
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "unsecure" => Ok(AuthenticationMode::Unsecure),
+            "secure" => Ok(AuthenticationMode::Secure),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Hex encoded key shared with the token issuer
+///
+/// Never printed, so debug output of the settings doesn't leak it
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PrivateKey(pub String);
+
+impl fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PrivateKey(<redacted>)")
+    }
+}
+
+/// Settings the server is hosted with
+///
+/// Loaded from an optional JSON file, then overridden by command-line flags.
+/// The private key is never taken as a flag, it is read from the config file,
+/// the `COBALT_PRIVATE_KEY` environment variable or a key file
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSettings {
+    pub bind_addr: SocketAddr,
+    pub public_addr: SocketAddr,
+    pub max_clients: usize,
+    pub tick_rate: f64,
+    pub protocol_id: u64,
+    pub asset_config: String,
+    pub authentication: AuthenticationMode,
+    pub private_key: Option<PrivateKey>,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            bind_addr: SocketAddr::from(([0, 0, 0, 0], 5000)),
+            public_addr: SocketAddr::from(([0, 0, 0, 0], 5000)),
+            max_clients: 64,
+            tick_rate: TICK_RATE,
+            protocol_id: PROTOCOL_ID,
+            asset_config: "assets.json".to_string(),
+            authentication: AuthenticationMode::Unsecure,
+            private_key: None,
+        }
+    }
+}
+
+impl ServerSettings {
+    /// Builds the settings from the command-line arguments, without the program name
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, SettingsError> {
+        let args: Vec<String> = args.into_iter().collect();
+
+        // the config file is the base the other flags override,
+        // so it is read first wherever it appears
+        let mut settings = match flag_value(&args, "--config")? {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+
+        if let Ok(private_key) = env::var(PRIVATE_KEY_VAR) {
+            settings.private_key = Some(PrivateKey(private_key));
+        }
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            if flag == "-h" || flag == "--help" {
+                return Err(SettingsError::Help);
+            }
+
+            let value = args
+                .next()
+                .ok_or_else(|| SettingsError::MissingValue(flag.clone()))?;
+
+            match flag.as_str() {
+                "--config" => {}
+                "--bind" => settings.bind_addr = parse(flag, value)?,
+                "--public-addr" => settings.public_addr = parse(flag, value)?,
+                "--max-clients" => settings.max_clients = parse(flag, value)?,
+                "--tick-rate" => settings.tick_rate = parse(flag, value)?,
+                "--protocol-id" => settings.protocol_id = parse(flag, value)?,
+                "--asset-config" => settings.asset_config = value.clone(),
+                "--auth" => settings.authentication = parse(flag, value)?,
+                "--key-file" => {
+                    let private_key = fs::read_to_string(value)
+                        .map_err(|error| SettingsError::ReadKey(value.clone(), error))?;
+                    settings.private_key = Some(PrivateKey(private_key.trim().to_string()));
+                }
+                _ => return Err(SettingsError::UnknownFlag(flag.clone())),
+            }
+        }
+
+        settings.validate()?;
+
+        Ok(settings)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, SettingsError> {
+        let path = path.as_ref();
+        let file = fs::read_to_string(path)
+            .map_err(|error| SettingsError::Read(path.display().to_string(), error))?;
+
+        serde_json::from_str(&file)
+            .map_err(|error| SettingsError::Parse(path.display().to_string(), error))
+    }
+
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if self.max_clients == 0 || self.max_clients > MAX_CLIENTS {
+            return Err(SettingsError::Invalid(format!(
+                "max clients must be between 1 and {}, got {}",
+                MAX_CLIENTS, self.max_clients
+            )));
+        }
+
+        if !self.tick_rate.is_finite() || self.tick_rate <= 0.0 || self.tick_rate > MAX_TICK_RATE {
+            return Err(SettingsError::Invalid(format!(
+                "tick rate must be above 0 and at most {} Hz, got {}",
+                MAX_TICK_RATE, self.tick_rate
+            )));
+        }
+
+        if self.public_addr.port() == 0 {
+            return Err(SettingsError::Invalid(
+                "public address must have a port".to_string(),
+            ));
+        }
+
+        if self.asset_config.is_empty() {
+            return Err(SettingsError::Invalid(
+                "asset config path must not be empty".to_string(),
+            ));
+        }
+
+        if self.authentication == AuthenticationMode::Secure || self.private_key.is_some() {
+            self.private_key()?;
+        }
+
+        // connect tokens name the address clients connect to, which can't be a wildcard
+        if self.authentication == AuthenticationMode::Secure
+            && self.public_addr.ip().is_unspecified()
+        {
+            return Err(SettingsError::Invalid(format!(
+                "secure authentication requires a public address clients can reach, got {}",
+                self.public_addr
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// The decoded private key
+    pub fn private_key(&self) -> Result<[u8; NETCODE_KEY_BYTES], SettingsError> {
+        let private_key = self.private_key.as_ref().ok_or_else(|| {
+            SettingsError::Invalid("secure authentication requires a private key".to_string())
+        })?;
+
+        decode_key(&private_key.0).ok_or_else(|| {
+            SettingsError::Invalid(format!(
+                "private key must be {} hex encoded bytes",
+                NETCODE_KEY_BYTES
+            ))
+        })
+    }
+
+    pub fn server_authentication(&self) -> Result<ServerAuthentication, SettingsError> {
+        Ok(match self.authentication {
+            AuthenticationMode::Unsecure => ServerAuthentication::Unsecure,
+            AuthenticationMode::Secure => ServerAuthentication::Secure {
+                private_key: self.private_key()?,
+            },
+        })
+    }
+
+    pub fn usage() -> &'static str {
+        USAGE
+    }
+}
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Result<Option<&'a String>, SettingsError> {
+    match args.iter().position(|arg| arg == flag) {
+        Some(index) => args
+            .get(index + 1)
+            .map(Some)
+            .ok_or_else(|| SettingsError::MissingValue(flag.to_string())),
+        None => Ok(None),
+    }
+}
+
+/// Hex encodes a private key
+pub fn encode_key(key: &[u8; NETCODE_KEY_BYTES]) -> String {
+    key.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_key(hex: &str) -> Option<[u8; NETCODE_KEY_BYTES]> {
+    if hex.len() != NETCODE_KEY_BYTES * 2 || !hex.is_ascii() {
+        return None;
+    }
+
+    let mut key = [0; NETCODE_KEY_BYTES];
+    for (byte, pair) in key.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+
+    Some(key)
+}
@@ -1,53 +1,90 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
 use bevy::prelude::*;
-use bevy_2d_collisions::components::CollisionGroup;
+use bevy_2d_collisions::components::{CollisionBox, CollisionGroup};
 use bevy_renet::renet::{
-    RenetServer,
+    transport::NetcodeServerTransport,
+    ClientId as RenetClientId, RenetServer,
     ServerEvent::{self, ClientConnected, ClientDisconnected},
 };
 
 use crate::{
     asset::{
-        enums::{Equipment, Sprites},
+        params::AssetManifest,
         resources::{AssetHandler, AssetsConfig},
     },
     client::resources::ClientId,
-    deck::card::equipment::components::ServerEquipmentBundle,
+    deck::card::equipment::components::{Equipment, Inventory, ServerEquipmentBundle},
     enums::{CollisionGroups, EntityState},
     input::resources::PlayerInput,
     networking::{
         channels::{ClientChannel, ServerChannel},
-        components::SyncedEntity,
-        models::NetworkedEntities,
+        codec,
+        components::{NetworkId, SyncedEntity},
+        models::{
+            Handshake, HandshakeResponse, InputPacket, PlayerUserData, QuantizedEntity, SnapshotAck,
+        },
         networking::ServerMessages,
+        resources::NetworkRegistry,
     },
     player::{
-        components::{Player, ServerPlayerBundle, Team},
+        components::{HitboxHistory, HitboxSample, Player, ServerPlayerBundle, Team},
         events::{CreatePlayerEvent, PlayerCommand, RemovePlayerEvent},
     },
+    round::resources::MatchSeed,
     server::{
         events::{ClientSentCommandEvent, ClientSentInputEvent},
-        resources::ServerLobby,
+        params::{RateLimiter, ServerMessenger},
+        resources::{
+            ClientRateLimits, LagCompensation, NetworkStats, PendingClient, PendingClients,
+            ServerLobby, ServerTick, SnapshotBaselines, Violation, ViolationAction,
+            ViolationPolicy,
+        },
     },
 };
 
-use super::events::{ClientConnectedEvent, ClientDisconnectedEvent, SyncEntityEvent};
+use serde::de::DeserializeOwned;
+
+use super::events::{
+    ClientConnectedEvent, ClientDisconnectedEvent, ClientViolationEvent, SyncEntityEvent,
+};
 
 pub fn server_update_system(
-    mut writer_client_connected: EventWriter<ClientConnectedEvent>,
     mut writer_client_disconnected: EventWriter<ClientDisconnectedEvent>,
-    mut writer_player_input: EventWriter<ClientSentInputEvent>,
-    mut writer_player_command: EventWriter<ClientSentCommandEvent>,
     mut server_events: EventReader<ServerEvent>,
-    mut server: ResMut<RenetServer>,
+    mut pending_clients: ResMut<PendingClients>,
+    mut rate_limits: ResMut<ClientRateLimits>,
+    mut lobby: ResMut<ServerLobby>,
+    time: Res<Time>,
 ) {
     for event in server_events.read() {
         match event {
+            // the client joins the game once its handshake is accepted
             ServerEvent::ClientConnected { client_id } => {
-                writer_client_connected.send(ClientConnectedEvent(ServerEvent::ClientConnected {
-                    client_id: *client_id,
-                }));
+                pending_clients.clients.insert(
+                    client_id.raw(),
+                    PendingClient {
+                        connected_at: time.elapsed(),
+                        rejected_at: None,
+                    },
+                );
             }
             ServerEvent::ClientDisconnected { client_id, reason } => {
+                rate_limits.clients.remove(&client_id.raw());
+                lobby.violations.remove(&client_id.raw());
+
+                // a client that never completed the handshake never joined
+                if pending_clients.clients.remove(&client_id.raw()).is_some() {
+                    println!(
+                        "Client {} disconnected during the handshake. {}",
+                        client_id, reason
+                    );
+                    continue;
+                }
+
                 writer_client_disconnected.send(ClientDisconnectedEvent(
                     ServerEvent::ClientDisconnected {
                         client_id: *client_id,
@@ -57,57 +94,321 @@ pub fn server_update_system(
             }
         }
     }
+}
+
+pub fn server_receive_messages_system(
+    mut writer_player_input: EventWriter<ClientSentInputEvent>,
+    mut writer_player_command: EventWriter<ClientSentCommandEvent>,
+    mut writer_client_violation: EventWriter<ClientViolationEvent>,
+    mut server: ResMut<RenetServer>,
+    mut baselines: ResMut<SnapshotBaselines>,
+    mut rate_limiter: RateLimiter,
+    time: Res<Time>,
+) {
+    let now = time.elapsed();
+
+    let mut violations = Vec::new();
 
     for client_id in server.clients_id() {
-        while let Some(message) = server.receive_message(client_id, ClientChannel::Input) {
-            let input: PlayerInput = bincode::deserialize(&message).unwrap();
-            writer_player_input.send(ClientSentInputEvent(input, client_id.raw()));
+        for input_packet in receive_messages::<InputPacket>(
+            &mut server,
+            client_id,
+            ClientChannel::Input,
+            &mut rate_limiter,
+            &mut violations,
+            now,
+        ) {
+            // a packet never carries more than the redundancy,
+            // the extra inputs of an oversized one are ignored
+            let skip = input_packet
+                .inputs
+                .len()
+                .saturating_sub(InputPacket::REDUNDANCY);
+            for packed_input in input_packet.inputs.iter().skip(skip) {
+                writer_player_input
+                    .send(ClientSentInputEvent(packed_input.unpack(), client_id.raw()));
+            }
         }
 
-        while let Some(message) = server.receive_message(client_id, ClientChannel::Command) {
-            let command: PlayerCommand = bincode::deserialize(&message).unwrap();
+        for command in receive_messages::<PlayerCommand>(
+            &mut server,
+            client_id,
+            ClientChannel::Command,
+            &mut rate_limiter,
+            &mut violations,
+            now,
+        ) {
             writer_player_command.send(ClientSentCommandEvent(command, client_id.raw()));
         }
+
+        for snapshot_ack in receive_messages::<SnapshotAck>(
+            &mut server,
+            client_id,
+            ClientChannel::SnapshotAck,
+            &mut rate_limiter,
+            &mut violations,
+            now,
+        ) {
+            baselines
+                .clients
+                .entry(client_id.raw())
+                .or_default()
+                .acknowledge(snapshot_ack.tick);
+        }
+
+        for violation in violations.drain(..) {
+            writer_client_violation.send(ClientViolationEvent(violation, client_id.raw()));
+        }
     }
 }
 
-pub fn server_network_sync(
+/// Checks the handshake of pending clients, letting matching clients join
+/// and disconnecting the ones that are rejected or never send one
+pub fn server_handshake_system(
+    mut writer_client_connected: EventWriter<ClientConnectedEvent>,
+    mut writer_client_violation: EventWriter<ClientViolationEvent>,
     mut server: ResMut<RenetServer>,
-    query: Query<(Entity, &Transform, &EntityState, &PlayerInput), With<SyncedEntity>>,
+    mut pending_clients: ResMut<PendingClients>,
+    mut rate_limiter: RateLimiter,
+    asset_manifest: AssetManifest,
+    time: Res<Time>,
 ) {
-    let mut networked_entities = NetworkedEntities::default();
-    for (entity, transform, entity_state, player_input) in query.iter() {
-        networked_entities.entities.push(entity);
-        networked_entities
-            .translations
-            .push(transform.translation.into());
-        networked_entities.aim_ats.push(player_input.aim.into());
-        networked_entities.states.push(*entity_state)
+    let now = time.elapsed();
+    let expected = asset_manifest.handshake();
+    let mut violations = Vec::new();
+
+    for client_id in server.clients_id() {
+        let handshakes = receive_messages::<Handshake>(
+            &mut server,
+            client_id,
+            ClientChannel::Handshake,
+            &mut rate_limiter,
+            &mut violations,
+            now,
+        );
+        for violation in violations.drain(..) {
+            writer_client_violation.send(ClientViolationEvent(violation, client_id.raw()));
+        }
+
+        // clients that already joined have no reason to send another handshake
+        let Some(pending_client) = pending_clients.clients.get_mut(&client_id.raw()) else {
+            continue;
+        };
+        let Some(handshake) = handshakes
+            .first()
+            .filter(|_| pending_client.rejected_at.is_none())
+        else {
+            continue;
+        };
+
+        let response = handshake.verify(
+            &expected,
+            asset_manifest.registry_table(),
+            rate_limiter.tick_rate(),
+        );
+        server.send_message(
+            client_id,
+            ServerChannel::Handshake,
+            codec::encode(&response),
+        );
+
+        match response {
+            HandshakeResponse::Accepted { .. } => {
+                pending_clients.clients.remove(&client_id.raw());
+                writer_client_connected.send(ClientConnectedEvent(ServerEvent::ClientConnected {
+                    client_id,
+                }));
+            }
+            HandshakeResponse::Rejected { reason } => {
+                println!("Rejected client {}. {}", client_id, reason);
+                pending_client.rejected_at = Some(now);
+            }
+        }
     }
 
-    let sync_message = bincode::serialize(&networked_entities).unwrap();
-    server.broadcast_message(ServerChannel::NetworkedEntities, sync_message);
+    let timeout = pending_clients.timeout;
+    let rejection_grace = pending_clients.rejection_grace;
+    for (client_id, pending_client) in pending_clients.clients.iter() {
+        let expired = match pending_client.rejected_at {
+            Some(rejected_at) => now.saturating_sub(rejected_at) > rejection_grace,
+            None => now.saturating_sub(pending_client.connected_at) > timeout,
+        };
+
+        if expired {
+            server.disconnect(RenetClientId::from_raw(*client_id));
+        }
+    }
 }
 
-pub fn on_demand_server_network_sync(
+/// Reads and decodes the messages a client sent on a channel
+///
+/// Messages past the channel's rate limit, and messages that fail to decode,
+/// are dropped and added to the client's violations
+fn receive_messages<T: DeserializeOwned>(
+    server: &mut RenetServer,
+    client_id: RenetClientId,
+    channel: ClientChannel,
+    rate_limiter: &mut RateLimiter,
+    violations: &mut Vec<Violation>,
+    now: Duration,
+) -> Vec<T> {
+    let mut messages = Vec::new();
+    let mut rate_limited = false;
+
+    while let Some(message) = server.receive_message(client_id, channel) {
+        if !rate_limiter.allow(client_id.raw(), channel, now) {
+            rate_limited = true;
+            continue;
+        }
+
+        match codec::decode(&message, channel.max_message_bytes()) {
+            Ok(decoded) => messages.push(decoded),
+            Err(error) => {
+                println!(
+                    "Dropped message from player {} on {:?}: {}",
+                    client_id, channel, error
+                );
+                violations.push(Violation::Malformed(channel));
+            }
+        }
+    }
+
+    // a flood counts once per update, its size is already bounded by the bucket
+    if rate_limited {
+        violations.push(Violation::RateLimited(channel));
+    }
+
+    messages
+}
+
+/// Scores the violations of each client on the lobby,
+/// warning or kicking the client when the policy says so
+pub fn enforce_violations_system(
+    mut reader_client_violation: EventReader<ClientViolationEvent>,
+    mut lobby: ResMut<ServerLobby>,
+    mut server_messenger: ServerMessenger,
+    violation_policy: Res<ViolationPolicy>,
+    time: Res<Time>,
+) {
+    for ClientViolationEvent(violation, client_id) in reader_client_violation.read() {
+        let action =
+            lobby.report_violation(*client_id, *violation, &violation_policy, time.elapsed());
+
+        match action {
+            ViolationAction::None => {}
+            ViolationAction::Warn => {
+                println!("Warning player {}: {}.", client_id, violation);
+                server_messenger.send(
+                    RenetClientId::from_raw(*client_id),
+                    ServerMessages::Warning(format!(
+                        "Suspicious activity detected ({}), continuing will get you kicked.",
+                        violation
+                    )),
+                );
+            }
+            ViolationAction::Kick => {
+                println!("Kicking player {}: {}.", client_id, violation);
+                server_messenger.disconnect(RenetClientId::from_raw(*client_id));
+            }
+        }
+    }
+}
+
+pub fn advance_server_tick(mut server_tick: ResMut<ServerTick>) {
+    server_tick.0 += 1;
+}
+
+/// Records each player's hitbox for the tick, kept as far back as shots may be rewound
+pub fn record_hitbox_history(
+    server_tick: Res<ServerTick>,
+    lag_compensation: Res<LagCompensation>,
+    fixed_time: Res<Time<Fixed>>,
+    mut query: Query<(&Transform, &CollisionBox, &mut HitboxHistory)>,
+) {
+    let capacity = lag_compensation.max_rewind_ticks(fixed_time.timestep()) as usize + 1;
+
+    for (transform, collision_box, mut hitbox_history) in &mut query {
+        hitbox_history.record(
+            HitboxSample {
+                tick: **server_tick,
+                translation: transform.translation.truncate(),
+                size: collision_box.size,
+            },
+            capacity,
+        );
+    }
+}
+
+pub fn server_network_sync(
     mut server: ResMut<RenetServer>,
+    mut baselines: ResMut<SnapshotBaselines>,
+    mut network_stats: ResMut<NetworkStats>,
     mut reader_forced_server_sync: EventReader<SyncEntityEvent>,
-    query: Query<(&Transform, &EntityState, &PlayerInput), With<SyncedEntity>>,
+    server_tick: Res<ServerTick>,
+    lobby: Res<ServerLobby>,
+    query: Query<(&NetworkId, &Transform, &EntityState, &PlayerInput), With<SyncedEntity>>,
 ) {
-    for sync_entity_event in reader_forced_server_sync.read() {
-        let mut networked_entities = NetworkedEntities::default();
-
-        if let Ok((transform, entity_state, player_input)) = query.get(sync_entity_event.entity) {
-            networked_entities.entities.push(sync_entity_event.entity);
-            networked_entities
-                .translations
-                .push(transform.translation.into());
-            networked_entities.aim_ats.push(player_input.aim.into());
-            networked_entities.states.push(*entity_state)
+    // forced entities are sent in full, regardless of the client's baseline
+    let forced: HashSet<NetworkId> = reader_forced_server_sync
+        .read()
+        .filter_map(|sync_entity_event| query.get(sync_entity_event.entity).ok())
+        .map(|(network_id, ..)| *network_id)
+        .collect();
+
+    let entities: HashMap<NetworkId, QuantizedEntity> = query
+        .iter()
+        .map(|(network_id, transform, entity_state, player_input)| {
+            (
+                *network_id,
+                QuantizedEntity::new(transform.translation, player_input.aim, *entity_state),
+            )
+        })
+        .collect();
+
+    // pending clients are not in the game yet
+    for client_id in server
+        .clients_id()
+        .into_iter()
+        .filter(|client_id| lobby.players.contains_key(&client_id.raw()))
+    {
+        let client_baseline = baselines.clients.entry(client_id.raw()).or_default();
+        let mut networked_entities = client_baseline.snapshot(**server_tick, &entities, &forced);
+
+        if let Some(player_input) = lobby
+            .players
+            .get(&client_id.raw())
+            .and_then(|player_entity| query.get(*player_entity).ok())
+            .map(|(.., player_input)| player_input)
+        {
+            networked_entities.last_processed_input = player_input.sequence;
         }
 
-        let sync_message = bincode::serialize(&networked_entities).unwrap();
-        server.broadcast_message(ServerChannel::NetworkedEntities, sync_message);
+        client_baseline.record(**server_tick, entities.clone());
+
+        let sync_message = codec::encode(&networked_entities);
+        network_stats.record(client_id.raw(), sync_message.len());
+        server.send_message(client_id, ServerChannel::NetworkedEntities, sync_message);
+    }
+}
+
+/// Records the display name each connecting client carries in its user data,
+/// falling back to a generated name when it has none
+pub fn register_player_name(
+    mut reader_client_connected: EventReader<ClientConnectedEvent>,
+    mut lobby: ResMut<ServerLobby>,
+    transport: Res<NetcodeServerTransport>,
+) {
+    for client_connected in reader_client_connected.read() {
+        if let ClientConnected { client_id } = client_connected.0 {
+            let name = transport
+                .user_data(client_id)
+                .and_then(|user_data| PlayerUserData::from_bytes(&user_data))
+                .filter(|user_data| user_data.id == client_id.raw())
+                .map(|user_data| user_data.name)
+                .unwrap_or_else(|| format!("Player {}", client_id));
+
+            lobby.names.insert(client_id.raw(), name);
+        }
     }
 }
 
@@ -116,37 +417,48 @@ pub fn client_connected_to_server(
     mut commands: Commands,
     mut reader_client_connected: EventReader<ClientConnectedEvent>,
     mut lobby: ResMut<ServerLobby>,
-    mut server: ResMut<RenetServer>,
+    mut server_messenger: ServerMessenger,
+    mut network_registry: ResMut<NetworkRegistry>,
     asset_handler: Res<AssetHandler>,
     asset_config: Res<AssetsConfig>,
-    players: Query<(Entity, &Player, &Transform, &Team)>,
+    match_seed: Res<MatchSeed>,
+    players: Query<(&NetworkId, &Player, &Transform, &Team)>,
 ) {
+    let mut team_counts: HashMap<Team, usize> = HashMap::new();
+    for (_, _, _, team) in &players {
+        *team_counts.entry(*team).or_default() += 1;
+    }
+
     for client_connected in reader_client_connected.read() {
         match client_connected.0 {
             ClientConnected { client_id } => {
-                println!("Player {} connected.", client_id);
+                println!(
+                    "Player {} ({}) connected.",
+                    lobby.names.get(&client_id.raw()).map_or("", String::as_str),
+                    client_id
+                );
 
                 // initialize the newly connected client with the current state of the players in the game
-                for (entity, player, transform, p_team) in &players {
+                for (network_id, player, transform, p_team) in &players {
                     let translation: [f32; 3] = transform.translation.into();
-                    let message =
-                        bincode::serialize(&ServerMessages::PlayerCreate(CreatePlayerEvent {
+                    server_messenger.send(
+                        client_id,
+                        ServerMessages::PlayerCreate(CreatePlayerEvent {
                             id: ClientId(player.id.raw()),
-                            entity,
+                            network_id: *network_id,
                             translation,
-                            team: (**p_team).into(),
-                        }))
-                        .unwrap();
-                    server.send_message(client_id, ServerChannel::ServerMessages, message);
+                            team: *p_team,
+                        }),
+                    );
                 }
 
                 // spawn the player on the server
 
-                let character_type = Sprites::Skeleton;
+                let character_type = &asset_config.sprites.character;
                 // Retrieve character assets from the already loaded resources
                 let (_texture, _animations, hitbox_config) = asset_handler
                     .textures
-                    .get(&character_type)
+                    .get(character_type)
                     .expect("unexpected character requested.");
 
                 let hitbox_config = hitbox_config.expect(&format!(
@@ -154,49 +466,68 @@ pub fn client_connected_to_server(
                     character_type
                 ));
 
-                // TODO: Change this, curently just auto balancing teams
-                let team = if lobby.players.len() % 2 == 0 {
-                    CollisionGroups::TeamAlpha as u32
-                } else {
-                    CollisionGroups::TeamBravo as u32
-                };
+                // new players join the team with the fewest players
+                let team = asset_config.teams.auto_assign(&team_counts);
+                *team_counts.entry(team).or_default() += 1;
 
-                let spawn_point = Vec3::new(0.0, 0.0, 0.0);
+                let spawn_point = asset_config
+                    .map
+                    .spawn_point(team, lobby.players.len() as u64);
+                let network_id = network_registry.allocate();
                 let player_entity = commands
-                    .spawn(ServerPlayerBundle::new(
-                        client_id,
-                        Transform::from_translation(spawn_point.clone()),
-                        Vec2::new(hitbox_config.width, hitbox_config.height),
-                        CollisionGroup {
-                            layer: CollisionGroups::Player as u32 | team,
-                            mask: 0,
-                        },
-                        Team(team.into()),
+                    .spawn((
+                        ServerPlayerBundle::new(
+                            client_id,
+                            Transform::from_translation(spawn_point.clone()),
+                            Vec2::new(hitbox_config.width, hitbox_config.height),
+                            CollisionGroup {
+                                layer: CollisionGroups::Player as u32 | team.layer(),
+                                mask: 0,
+                            },
+                            team,
+                        ),
+                        network_id,
                     ))
-                    .with_children(|parent| {
-                        parent.spawn(ServerEquipmentBundle::new(
+                    .id();
+
+                // one equipment entity per inventory slot, the first one in hand,
+                // each spraying by its own seed
+                let slots = asset_config
+                    .inventory
+                    .loadout
+                    .iter()
+                    .enumerate()
+                    .map(|(slot, equipment)| {
+                        let stats =
                             asset_config
                                 .stats
                                 .equipment
-                                .get(&Equipment::AK47)
-                                .expect("Could not find AK47 in equipment config.")
-                                .into(),
-                        ));
+                                .get(equipment)
+                                .unwrap_or_else(|| {
+                                    panic!("Could not find {:?} in equipment config.", equipment)
+                                });
+
+                        commands
+                            .spawn(ServerEquipmentBundle::new(
+                                Equipment::from(stats)
+                                    .seeded(match_seed.spray_seed(network_id, slot)),
+                            ))
+                            .set_parent(player_entity)
+                            .id()
                     })
-                    .id();
+                    .collect();
+                commands.entity(player_entity).insert(Inventory::new(slots));
 
                 lobby.players.insert(client_id.raw(), player_entity);
+                network_registry.insert(network_id, player_entity);
 
                 // send the player entity to the clients
-                let message =
-                    bincode::serialize(&ServerMessages::PlayerCreate(CreatePlayerEvent {
-                        id: ClientId(client_id.raw()),
-                        entity: player_entity,
-                        translation: spawn_point.to_array(),
-                        team,
-                    }))
-                    .unwrap();
-                server.broadcast_message(ServerChannel::ServerMessages, message);
+                server_messenger.broadcast(ServerMessages::PlayerCreate(CreatePlayerEvent {
+                    id: ClientId(client_id.raw()),
+                    network_id,
+                    translation: spawn_point.to_array(),
+                    team,
+                }));
             }
             _ => {}
         }
@@ -207,23 +538,28 @@ pub fn client_disconnected(
     mut commands: Commands,
     mut reader_client_disconnected: EventReader<ClientDisconnectedEvent>,
     mut lobby: ResMut<ServerLobby>,
-    mut server: ResMut<RenetServer>,
+    mut server_messenger: ServerMessenger,
+    mut network_registry: ResMut<NetworkRegistry>,
+    mut baselines: ResMut<SnapshotBaselines>,
+    mut network_stats: ResMut<NetworkStats>,
 ) {
     for client_disconnected in reader_client_disconnected.read() {
         match client_disconnected.0 {
             ClientDisconnected { client_id, reason } => {
                 println!("Player {} disconnected. {}", client_id, reason);
 
+                lobby.names.remove(&client_id.raw());
+                baselines.clients.remove(&client_id.raw());
+                network_stats.clients.remove(&client_id.raw());
+
                 if let Some(player_entity) = lobby.players.remove(&client_id.raw()) {
-                    commands.entity(player_entity).despawn();
+                    network_registry.remove_entity(&player_entity);
+                    commands.entity(player_entity).despawn_recursive();
                 }
 
-                let message =
-                    bincode::serialize(&ServerMessages::PlayerRemove(RemovePlayerEvent {
-                        id: ClientId(client_id.raw()),
-                    }))
-                    .unwrap();
-                server.broadcast_message(ServerChannel::ServerMessages, message);
+                server_messenger.broadcast(ServerMessages::PlayerRemove(RemovePlayerEvent {
+                    id: ClientId(client_id.raw()),
+                }));
             }
             _ => println!("Unexpected server event in client disconnect event stream."),
         }
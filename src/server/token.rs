@@ -0,0 +1,121 @@
+use std::{
+    fs::File,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use bevy_renet::renet::transport::ConnectToken;
+
+use crate::{
+    networking::models::PlayerUserData,
+    settings::{parse, SettingsError},
+};
+
+use super::settings::{AuthenticationMode, ServerSettings};
+
+const USAGE: &str = "\
+Usage: server token --client-id <ID> --name <NAME> [OPTIONS]
+
+Mints a connect token for the secure authentication mode, signed with
+the private key from the settings. Server options such as --config,
+--public-addr, --protocol-id and --key-file are accepted as well.
+
+Options:
+  --client-id <ID>        Id the player connects with
+  --name <NAME>           Display name of the player
+  --expire <SECONDS>      How long the token can be used to connect, 300 by default
+  --out <PATH>            File the token is written to, connect_token.bin by default
+  -h, --help              Print this message";
+
+/// A request to mint a connect token for a player
+#[derive(Debug)]
+pub struct TokenRequest {
+    pub settings: ServerSettings,
+    pub user_data: PlayerUserData,
+    pub expire: Duration,
+    pub out: PathBuf,
+}
+
+impl TokenRequest {
+    /// Builds the request from the arguments following the `token` subcommand
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, SettingsError> {
+        let mut client_id = None;
+        let mut name = None;
+        let mut expire = Duration::from_secs(300);
+        let mut out = PathBuf::from("connect_token.bin");
+        let mut server_args = Vec::new();
+
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            if flag == "-h" || flag == "--help" {
+                return Err(SettingsError::Help);
+            }
+
+            let value = args
+                .next()
+                .ok_or_else(|| SettingsError::MissingValue(flag.clone()))?;
+
+            match flag.as_str() {
+                "--client-id" => client_id = Some(parse(&flag, &value)?),
+                "--name" => name = Some(value),
+                "--expire" => expire = Duration::from_secs(parse(&flag, &value)?),
+                "--out" => out = PathBuf::from(value),
+                _ => server_args.extend([flag, value]),
+            }
+        }
+
+        let mut settings = ServerSettings::from_args(server_args)?;
+        // tokens are always signed, whatever mode the settings are in
+        settings.authentication = AuthenticationMode::Secure;
+        settings.validate()?;
+
+        Ok(Self {
+            settings,
+            user_data: PlayerUserData {
+                id: client_id.ok_or_else(|| SettingsError::MissingValue("--client-id".into()))?,
+                name: name.ok_or_else(|| SettingsError::MissingValue("--name".into()))?,
+            },
+            expire,
+            out,
+        })
+    }
+
+    pub fn mint(&self) -> Result<ConnectToken, SettingsError> {
+        let user_data = self.user_data.to_bytes().ok_or_else(|| {
+            SettingsError::Token(format!(
+                "name must be at most {} characters",
+                PlayerUserData::MAX_NAME_LENGTH
+            ))
+        })?;
+        let current_time = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_err(|_| SettingsError::Clock)?;
+
+        ConnectToken::generate(
+            current_time,
+            self.settings.protocol_id,
+            self.expire.as_secs(),
+            self.user_data.id,
+            15,
+            vec![self.settings.public_addr],
+            Some(&user_data),
+            &self.settings.private_key()?,
+        )
+        .map_err(|error| SettingsError::Token(error.to_string()))
+    }
+
+    /// Mints the token and writes it to the output file
+    pub fn write(&self) -> Result<(), SettingsError> {
+        let connect_token = self.mint()?;
+        let mut file = File::create(&self.out)
+            .map_err(|error| SettingsError::Token(format!("{}: {}", self.out.display(), error)))?;
+
+        connect_token
+            .write(&mut file)
+            .map_err(|error| SettingsError::Token(format!("{}: {}", self.out.display(), error)))
+    }
+
+    pub fn usage() -> &'static str {
+        USAGE
+    }
+}
@@ -0,0 +1,53 @@
+use std::{fmt, io, net::SocketAddr, str::FromStr};
+
+/// Parses the value of a command-line flag
+pub fn parse<T: FromStr>(flag: &str, value: &str) -> Result<T, SettingsError> {
+    value
+        .parse()
+        .map_err(|_| SettingsError::InvalidValue(flag.to_string(), value.to_string()))
+}
+
+/// Errors raised while building settings from a file or flags, or hosting with them
+#[derive(Debug)]
+pub enum SettingsError {
+    /// The help flag was passed, the usage should be printed
+    Help,
+    UnknownFlag(String),
+    MissingValue(String),
+    InvalidValue(String, String),
+    Read(String, io::Error),
+    ReadKey(String, io::Error),
+    Parse(String, serde_json::Error),
+    Invalid(String),
+    Bind(SocketAddr, io::Error),
+    Clock,
+    Token(String),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Help => write!(f, "help requested"),
+            SettingsError::UnknownFlag(flag) => write!(f, "unknown flag {}", flag),
+            SettingsError::MissingValue(flag) => write!(f, "{} requires a value", flag),
+            SettingsError::InvalidValue(flag, value) => {
+                write!(f, "invalid value {:?} for {}", value, flag)
+            }
+            SettingsError::Read(path, error) => {
+                write!(f, "could not read settings file {}: {}", path, error)
+            }
+            SettingsError::ReadKey(path, error) => {
+                write!(f, "could not read private key file {}: {}", path, error)
+            }
+            SettingsError::Parse(path, error) => {
+                write!(f, "could not parse settings file {}: {}", path, error)
+            }
+            SettingsError::Invalid(reason) => write!(f, "invalid settings: {}", reason),
+            SettingsError::Bind(addr, error) => write!(f, "could not bind {}: {}", addr, error),
+            SettingsError::Clock => write!(f, "system clock is set before the unix epoch"),
+            SettingsError::Token(reason) => write!(f, "could not create connect token: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
@@ -1,7 +1,11 @@
+use std::{fmt, time::Duration};
+
 use bevy::{
-    ecs::component::Component,
+    ecs::{component::Component, entity::Entity},
     prelude::{Deref, DerefMut},
+    time::{Timer, TimerMode},
 };
+use serde::{Deserialize, Serialize};
 
 /**
  * Speed
@@ -36,3 +40,210 @@ impl Default for Health {
         }
     }
 }
+
+/**
+ * Status Kind
+ *
+ * The kinds of timed effects an entity can be under
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StatusKind {
+    /// Lowers speed by a fraction per stack
+    Slowed,
+    /// Raises speed by a fraction per stack
+    Hasted,
+    /// Deals damage per second per stack, every tick interval
+    Burning,
+    /// Can't move or fire
+    Stunned,
+    /// Can't be damaged
+    Invulnerable,
+}
+
+impl fmt::Display for StatusKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/**
+ * Stacking Policy
+ *
+ * How a status effect combines with one of the same kind already applied
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StackingPolicy {
+    /// The new effect replaces the old one
+    #[default]
+    Refresh,
+    /// The old effect gains a stack, up to a maximum, and its duration restarts
+    StackIntensity,
+    /// Both effects run side by side, each with its own duration
+    Independent,
+}
+
+/**
+ * Status Effect
+ *
+ * A timed effect on an entity, `source` is the entity that applied it
+ */
+#[derive(Debug, Clone)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    /// Strength of one stack, its meaning depends on the kind
+    pub magnitude: f32,
+    pub stacks: u32,
+    pub duration: Timer,
+    /// Fires the effect's periodic ticks, effects without one never tick
+    pub tick: Option<Timer>,
+    pub source: Option<Entity>,
+}
+
+impl StatusEffect {
+    pub fn new(
+        kind: StatusKind,
+        magnitude: f32,
+        seconds: f32,
+        tick_interval: f32,
+        source: Option<Entity>,
+    ) -> Self {
+        Self {
+            kind,
+            magnitude,
+            stacks: 1,
+            duration: Timer::from_seconds(seconds, TimerMode::Once),
+            tick: (tick_interval > 0.0)
+                .then(|| Timer::from_seconds(tick_interval, TimerMode::Repeating)),
+            source,
+        }
+    }
+
+    pub fn intensity(&self) -> f32 {
+        self.magnitude * self.stacks as f32
+    }
+
+    pub fn remaining(&self) -> f32 {
+        self.duration.remaining_secs()
+    }
+}
+
+/**
+ * Status Effects
+ *
+ * The timed effects an entity is under, derived stats are
+ * recomputed from them every tick
+ */
+#[derive(Component, Debug, Default)]
+pub struct StatusEffects {
+    effects: Vec<StatusEffect>,
+    /// Whether effects were applied or ran out since the last sync
+    changed: bool,
+}
+
+impl StatusEffects {
+    /// Applies an effect following the stacking policy of its kind,
+    /// `max_stacks` caps the stacks or the side by side effects of the kind
+    pub fn apply(&mut self, effect: StatusEffect, stacking: StackingPolicy, max_stacks: u32) {
+        let max_stacks = max_stacks.max(1);
+        let kind = effect.kind;
+        let same_kind = |existing: &&mut StatusEffect| existing.kind == kind;
+
+        match stacking {
+            StackingPolicy::Refresh => match self.effects.iter_mut().find(same_kind) {
+                Some(existing) => *existing = effect,
+                None => self.effects.push(effect),
+            },
+            StackingPolicy::StackIntensity => match self.effects.iter_mut().find(same_kind) {
+                Some(existing) => {
+                    existing.stacks = (existing.stacks + 1).min(max_stacks);
+                    existing.magnitude = effect.magnitude;
+                    existing.duration = effect.duration;
+                    existing.source = effect.source;
+                }
+                None => self.effects.push(effect),
+            },
+            StackingPolicy::Independent => {
+                // the effect closest to running out makes room for the new one
+                let instances = self.effects.iter_mut().filter(same_kind).count();
+                if instances >= max_stacks as usize {
+                    if let Some(index) = self
+                        .effects
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, existing)| existing.kind == kind)
+                        .min_by(|(_, a), (_, b)| a.remaining().total_cmp(&b.remaining()))
+                        .map(|(index, _)| index)
+                    {
+                        self.effects.remove(index);
+                    }
+                }
+                self.effects.push(effect);
+            }
+        }
+
+        self.changed = true;
+    }
+
+    /// Advances every effect, returning the ones that ticked
+    /// and dropping the ones that ran out
+    pub fn tick(&mut self, delta: Duration) -> Vec<StatusEffect> {
+        let mut ticked = Vec::new();
+
+        for effect in &mut self.effects {
+            effect.duration.tick(delta);
+
+            if let Some(tick) = &mut effect.tick {
+                if tick.tick(delta).just_finished() {
+                    ticked.push(effect.clone());
+                }
+            }
+        }
+
+        let count = self.effects.len();
+        self.effects.retain(|effect| !effect.duration.finished());
+        self.changed |= self.effects.len() != count;
+
+        ticked
+    }
+
+    pub fn clear(&mut self) {
+        self.changed |= !self.effects.is_empty();
+        self.effects.clear();
+    }
+
+    /// Replaces every effect, as received from the server
+    pub fn replace(&mut self, effects: Vec<StatusEffect>) {
+        self.effects = effects;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StatusEffect> {
+        self.effects.iter()
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    /// The summed intensity of every effect of a kind
+    pub fn intensity(&self, kind: StatusKind) -> f32 {
+        self.effects
+            .iter()
+            .filter(|effect| effect.kind == kind)
+            .map(StatusEffect::intensity)
+            .sum()
+    }
+
+    /// The factor applied to the base speed
+    pub fn speed_multiplier(&self) -> f32 {
+        if self.has(StatusKind::Stunned) {
+            return 0.0;
+        }
+
+        (1.0 + self.intensity(StatusKind::Hasted) - self.intensity(StatusKind::Slowed)).max(0.0)
+    }
+
+    /// Whether the effects changed since the last call
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+}
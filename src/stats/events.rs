@@ -0,0 +1,81 @@
+use bevy::prelude::{Entity, Event};
+use serde::{Deserialize, Serialize};
+
+use crate::networking::components::NetworkId;
+
+use super::components::{StatusEffect, StatusKind};
+
+/**
+ * Apply Status Event
+ *
+ * A Bevy Event to inform server systems
+ * a status effect should be applied to an entity,
+ * stacking with the effects it is already under
+ */
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ApplyStatusEvent {
+    pub target: Entity,
+    pub kind: StatusKind,
+    pub magnitude: f32,
+    pub seconds: f32,
+    pub source: Option<Entity>,
+}
+
+/**
+ * Status Tick Event
+ *
+ * A Bevy Event to inform server systems
+ * a status effect ticked, `amount` is its intensity
+ * over the elapsed tick interval
+ */
+#[derive(Event, Debug, Clone, Copy)]
+pub struct StatusTickEvent {
+    pub target: Entity,
+    pub kind: StatusKind,
+    pub amount: f32,
+    pub source: Option<Entity>,
+}
+
+/// Serializable struct
+/// a status effect as sent to the clients, without its source
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct StatusEffectState {
+    pub kind: StatusKind,
+    pub magnitude: f32,
+    pub stacks: u32,
+    /// Seconds left before the effect runs out
+    pub remaining: f32,
+}
+
+impl From<&StatusEffect> for StatusEffectState {
+    fn from(effect: &StatusEffect) -> Self {
+        Self {
+            kind: effect.kind,
+            magnitude: effect.magnitude,
+            stacks: effect.stacks,
+            remaining: effect.remaining(),
+        }
+    }
+}
+
+impl From<StatusEffectState> for StatusEffect {
+    fn from(state: StatusEffectState) -> Self {
+        Self {
+            stacks: state.stacks.max(1),
+            ..StatusEffect::new(state.kind, state.magnitude, state.remaining, 0.0, None)
+        }
+    }
+}
+
+/**
+ * Status Effects Event
+ *
+ * A Bevy Event to inform client systems
+ * the status effects of an entity changed, contains the corresponding
+ * server message
+ */
+#[derive(Event, Debug, Serialize, Deserialize, Clone)]
+pub struct StatusEffectsEvent {
+    pub network_id: NetworkId,
+    pub effects: Vec<StatusEffectState>,
+}
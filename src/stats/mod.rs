@@ -1,9 +1,66 @@
-use bevy::app::{App, Plugin};
+use bevy::{
+    app::{App, FixedUpdate, Plugin, Update},
+    ecs::schedule::{common_conditions::in_state, IntoSystemConfigs},
+};
+
+use crate::{
+    enums::GameState,
+    networking::{is_client, is_server, sets::SimulationSet},
+};
+
+use self::{
+    events::{ApplyStatusEvent, StatusEffectsEvent, StatusTickEvent},
+    systems::{
+        apply_status_system, clear_status_on_death_system, on_status_effects,
+        recompute_stats_system, status_tint_system, sync_status_effects_system,
+        tick_status_effects_system,
+    },
+};
 
 pub mod components;
+pub mod events;
+mod systems;
 
 pub struct StatsPlugin;
 
 impl Plugin for StatsPlugin {
-    fn build(&self, _app: &mut App) {}
+    fn build(&self, app: &mut App) {
+        // the client predicts its own speed, so both sides run the effects
+        app.add_systems(
+            FixedUpdate,
+            (tick_status_effects_system, recompute_stats_system)
+                .chain()
+                .before(SimulationSet::Input)
+                .run_if(in_state(GameState::Gameloop)),
+        );
+
+        app.add_systems(
+            FixedUpdate,
+            apply_status_system
+                .in_set(SimulationSet::Gameplay)
+                .run_if(is_server())
+                .run_if(in_state(GameState::Gameloop)),
+        );
+
+        app.add_systems(
+            FixedUpdate,
+            (clear_status_on_death_system, sync_status_effects_system)
+                .chain()
+                .in_set(SimulationSet::Sync)
+                .run_if(is_server())
+                .run_if(in_state(GameState::Gameloop)),
+        );
+
+        app.add_systems(
+            Update,
+            (on_status_effects, status_tint_system)
+                .chain()
+                .run_if(is_client())
+                .run_if(in_state(GameState::Gameloop)),
+        );
+
+        app.add_event::<ApplyStatusEvent>();
+        app.add_event::<StatusTickEvent>();
+        app.add_event::<StatusEffectsEvent>();
+    }
 }
@@ -0,0 +1,144 @@
+use bevy::{
+    ecs::{
+        entity::Entity,
+        event::{EventReader, EventWriter},
+        query::{Added, Without},
+        system::{Query, Res},
+    },
+    render::color::Color,
+    sprite::TextureAtlasSprite,
+    time::Time,
+};
+
+use crate::{
+    asset::resources::AssetsConfig,
+    networking::{components::NetworkId, networking::ServerMessages, resources::NetworkRegistry},
+    physics::components::Velocity,
+    player::components::Death,
+    server::params::ServerMessenger,
+};
+
+use super::{
+    components::{StatusEffect, StatusEffects, StatusKind},
+    events::{ApplyStatusEvent, StatusEffectsEvent, StatusTickEvent},
+};
+
+/// Applies status effects to living entities,
+/// following the stacking policy configured for their kind
+pub fn apply_status_system(
+    mut reader_apply_status: EventReader<ApplyStatusEvent>,
+    asset_config: Res<AssetsConfig>,
+    mut query: Query<&mut StatusEffects, Without<Death>>,
+) {
+    for apply_status in reader_apply_status.read() {
+        let Ok(mut status_effects) = query.get_mut(apply_status.target) else {
+            continue;
+        };
+        let config = asset_config.status.effect(apply_status.kind);
+
+        status_effects.apply(
+            StatusEffect::new(
+                apply_status.kind,
+                apply_status.magnitude,
+                apply_status.seconds,
+                config.tick_interval,
+                apply_status.source,
+            ),
+            config.stacking,
+            config.max_stacks,
+        );
+    }
+}
+
+/// Advances status effects and reports the ones that ticked
+pub fn tick_status_effects_system(
+    time: Res<Time>,
+    mut writer_status_tick: EventWriter<StatusTickEvent>,
+    mut query: Query<(Entity, &mut StatusEffects), Without<Death>>,
+) {
+    for (entity, mut status_effects) in &mut query {
+        for effect in status_effects.tick(time.delta()) {
+            let interval = effect
+                .tick
+                .as_ref()
+                .map_or(0.0, |tick| tick.duration().as_secs_f32());
+
+            writer_status_tick.send(StatusTickEvent {
+                target: entity,
+                kind: effect.kind,
+                amount: effect.intensity() * interval,
+                source: effect.source,
+            });
+        }
+    }
+}
+
+/// Recomputes the stats derived from status effects, every tick
+pub fn recompute_stats_system(mut query: Query<(&StatusEffects, &mut Velocity)>) {
+    for (status_effects, mut velocity) in &mut query {
+        *velocity.current_speed = *velocity.base_speed * status_effects.speed_multiplier();
+    }
+}
+
+/// Lifts every status effect from entities that just died
+pub fn clear_status_on_death_system(mut query: Query<&mut StatusEffects, Added<Death>>) {
+    for mut status_effects in &mut query {
+        status_effects.clear();
+    }
+}
+
+/// Sends the status effects of entities to the clients whenever they change
+pub fn sync_status_effects_system(
+    mut server_messenger: ServerMessenger,
+    mut query: Query<(&NetworkId, &mut StatusEffects)>,
+) {
+    for (network_id, mut status_effects) in &mut query {
+        if !status_effects.take_changed() {
+            continue;
+        }
+
+        server_messenger.broadcast(ServerMessages::StatusEffects(StatusEffectsEvent {
+            network_id: *network_id,
+            effects: status_effects.iter().map(Into::into).collect(),
+        }));
+    }
+}
+
+pub fn on_status_effects(
+    mut reader_status_effects: EventReader<StatusEffectsEvent>,
+    network_registry: Res<NetworkRegistry>,
+    mut query: Query<&mut StatusEffects>,
+) {
+    for status_effects_event in reader_status_effects.read() {
+        if let Some(Ok(mut status_effects)) = network_registry
+            .entity(&status_effects_event.network_id)
+            .map(|entity| query.get_mut(entity))
+        {
+            status_effects.replace(
+                status_effects_event
+                    .effects
+                    .iter()
+                    .map(|&state| state.into())
+                    .collect(),
+            );
+        }
+    }
+}
+
+/// Tints entities by the most visible status effect they are under
+pub fn status_tint_system(mut query: Query<(&StatusEffects, &mut TextureAtlasSprite)>) {
+    let tints = [
+        (StatusKind::Stunned, Color::rgb(1.0, 0.9, 0.3)),
+        (StatusKind::Burning, Color::rgb(1.0, 0.5, 0.3)),
+        (StatusKind::Slowed, Color::rgb(0.5, 0.7, 1.0)),
+        (StatusKind::Hasted, Color::rgb(0.6, 1.0, 0.6)),
+        (StatusKind::Invulnerable, Color::rgba(1.0, 1.0, 1.0, 0.6)),
+    ];
+
+    for (status_effects, mut sprite) in &mut query {
+        sprite.color = tints
+            .iter()
+            .find(|(kind, _)| status_effects.has(*kind))
+            .map_or(Color::WHITE, |(_, tint)| *tint);
+    }
+}
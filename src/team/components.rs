@@ -0,0 +1,12 @@
+use bevy::{
+    prelude::{Component, Deref, DerefMut},
+    time::Timer,
+};
+
+/**
+ * Team Change Cooldown
+ *
+ * Counts down until a player that switched teams may switch again
+ */
+#[derive(Component, Debug, Deref, DerefMut)]
+pub struct TeamChangeCooldown(pub Timer);
@@ -0,0 +1,17 @@
+use bevy::prelude::Event;
+use serde::{Deserialize, Serialize};
+
+use crate::{networking::components::NetworkId, player::components::Team};
+
+/**
+ * Player Team Event
+ *
+ * A Bevy Event to inform client systems
+ * a player switched teams, contains the corresponding
+ * server message
+ */
+#[derive(Event, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PlayerTeamEvent {
+    pub network_id: NetworkId,
+    pub team: Team,
+}
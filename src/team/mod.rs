@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+
+use crate::{
+    client::sets::Connected,
+    enums::GameState,
+    networking::{is_client, is_server},
+};
+
+use self::{
+    events::PlayerTeamEvent,
+    systems::{join_team_system, on_player_team, tick_team_change_cooldowns},
+};
+
+pub mod components;
+pub mod events;
+mod systems;
+
+pub struct TeamPlugin;
+
+impl Plugin for TeamPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (join_team_system, tick_team_change_cooldowns)
+                .run_if(is_server())
+                .run_if(in_state(GameState::Gameloop)),
+        );
+
+        app.add_systems(
+            Update,
+            on_player_team
+                .run_if(is_client())
+                .run_if(in_state(GameState::Gameloop))
+                .in_set(Connected),
+        );
+
+        app.add_event::<PlayerTeamEvent>();
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use bevy::{
+    ecs::{
+        entity::Entity,
+        event::EventReader,
+        query::Has,
+        system::{Commands, Query, Res},
+    },
+    render::view::Visibility,
+    time::{Time, Timer, TimerMode},
+};
+use bevy_2d_collisions::components::CollisionGroup;
+use bevy_renet::renet::ClientId;
+
+use crate::{
+    asset::resources::AssetsConfig,
+    enums::CollisionGroups,
+    networking::{components::NetworkId, networking::ServerMessages, resources::NetworkRegistry},
+    player::{
+        components::{Death, Respawn, Team},
+        events::PlayerCommand,
+    },
+    server::{events::ClientSentCommandEvent, params::ServerMessenger, resources::ServerLobby},
+};
+
+use super::{components::TeamChangeCooldown, events::PlayerTeamEvent};
+
+/// Moves players to the team they asked for when the teams stay balanced,
+/// players that are alive respawn at their new team's spawn points
+pub fn join_team_system(
+    mut commands: Commands,
+    mut reader_client_sent_command: EventReader<ClientSentCommandEvent>,
+    mut server_messenger: ServerMessenger,
+    lobby: Res<ServerLobby>,
+    asset_config: Res<AssetsConfig>,
+    mut team_query: Query<(&mut Team, &mut CollisionGroup, &NetworkId)>,
+    state_query: Query<(Has<TeamChangeCooldown>, Has<Death>)>,
+) {
+    let config = &asset_config.teams;
+
+    for client_sent_command in reader_client_sent_command.read() {
+        let PlayerCommand::JoinTeam { team: requested } = client_sent_command.0 else {
+            continue;
+        };
+        let client_id = client_sent_command.1;
+
+        let Some(&entity) = lobby.players.get(&client_id) else {
+            continue;
+        };
+        let Ok((cooling_down, dead)) = state_query.get(entity) else {
+            continue;
+        };
+
+        if cooling_down {
+            server_messenger.send(
+                ClientId::from_raw(client_id),
+                ServerMessages::Warning("You can't switch teams again yet".to_string()),
+            );
+            continue;
+        }
+
+        let mut team_counts: HashMap<Team, usize> = HashMap::new();
+        for (team, _, _) in &team_query {
+            *team_counts.entry(*team).or_default() += 1;
+        }
+
+        let Ok((mut team, mut collision_group, network_id)) = team_query.get_mut(entity) else {
+            continue;
+        };
+
+        let requested = config.requested(requested);
+        if let Some(error) = config.join_error(*team, requested, &team_counts) {
+            server_messenger.send(
+                ClientId::from_raw(client_id),
+                ServerMessages::Warning(error),
+            );
+            continue;
+        }
+
+        println!("Player {} joined {}.", client_id, requested);
+
+        *team = requested;
+        collision_group.layer = CollisionGroups::Player as u32 | requested.layer();
+
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.insert(TeamChangeCooldown(Timer::from_seconds(
+            config.change_cooldown,
+            TimerMode::Once,
+        )));
+        if !dead {
+            entity_commands.insert(Respawn(Timer::from_seconds(0.0, TimerMode::Once)));
+        }
+
+        server_messenger.broadcast(ServerMessages::PlayerTeam(PlayerTeamEvent {
+            network_id: *network_id,
+            team: requested,
+        }));
+    }
+}
+
+pub fn tick_team_change_cooldowns(
+    mut commands: Commands,
+    dt: Res<Time>,
+    mut query: Query<(Entity, &mut TeamChangeCooldown)>,
+) {
+    for (entity, mut team_change_cooldown) in &mut query {
+        if team_change_cooldown.tick(dt.delta()).finished() {
+            commands.entity(entity).remove::<TeamChangeCooldown>();
+        }
+    }
+}
+
+pub fn on_player_team(
+    mut reader_player_team: EventReader<PlayerTeamEvent>,
+    network_registry: Res<NetworkRegistry>,
+    mut query: Query<(&mut Team, &mut CollisionGroup, &mut Visibility)>,
+) {
+    for player_team in reader_player_team.read() {
+        let Some(entity) = network_registry.entity(&player_team.network_id) else {
+            continue;
+        };
+        let Ok((mut team, mut collision_group, mut visibility)) = query.get_mut(entity) else {
+            continue;
+        };
+
+        *team = player_team.team;
+        collision_group.layer = CollisionGroups::Player as u32 | player_team.team.layer();
+
+        // spectators watch without being seen
+        *visibility = if player_team.team.is_playing() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
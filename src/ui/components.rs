@@ -0,0 +1,73 @@
+use bevy::ecs::component::Component;
+
+/**
+ * Connection Screen
+ *
+ * Component marking the entities of the connecting and disconnected screens
+ */
+#[derive(Component, Default)]
+pub struct ConnectionScreen;
+
+/**
+ * Connection Status
+ *
+ * Component marking the text describing the connection progress
+ */
+#[derive(Component, Default)]
+pub struct ConnectionStatus;
+
+/**
+ * Hud
+ *
+ * Component marking the entities of the in game overlay
+ */
+#[derive(Component, Default)]
+pub struct Hud;
+
+/**
+ * Kill Feed Text
+ *
+ * Component marking the text listing the latest kills
+ */
+#[derive(Component, Default)]
+pub struct KillFeedText;
+
+/**
+ * Scoreboard Panel
+ *
+ * Component marking the scoreboard, shown while Tab is held
+ */
+#[derive(Component, Default)]
+pub struct ScoreboardPanel;
+
+/**
+ * Scoreboard Text
+ *
+ * Component marking the text listing the players' scores
+ */
+#[derive(Component, Default)]
+pub struct ScoreboardText;
+
+/**
+ * Match Status Text
+ *
+ * Component marking the text showing the match phase and its countdown
+ */
+#[derive(Component, Default)]
+pub struct MatchStatusText;
+
+/**
+ * Hand Text
+ *
+ * Component marking the text listing the cards in the player's hand
+ */
+#[derive(Component, Default)]
+pub struct HandText;
+
+/**
+ * Status Text
+ *
+ * Component marking the text listing the status effects the player is under
+ */
+#[derive(Component, Default)]
+pub struct StatusText;
@@ -1,12 +1,17 @@
 use bevy::{
     app::{App, Plugin, Update},
-    ecs::schedule::{common_conditions::in_state, IntoSystemConfigs},
+    ecs::schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter, OnExit},
 };
 
 use crate::enums::GameState;
 
-use self::systems::health_bar_update;
+use self::systems::{
+    connecting_screen_update, despawn_connection_screen, despawn_hud, hand_update,
+    health_bar_update, kill_feed_update, match_status_update, scoreboard_update,
+    spawn_connecting_screen, spawn_disconnected_screen, spawn_hud, status_update,
+};
 
+pub mod components;
 mod systems;
 
 pub struct UiPlugin;
@@ -15,7 +20,27 @@ impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            (health_bar_update).run_if(in_state(GameState::Gameloop)),
+            (
+                health_bar_update,
+                kill_feed_update,
+                hand_update,
+                status_update,
+                match_status_update,
+                scoreboard_update,
+            )
+                .run_if(in_state(GameState::Gameloop)),
+        );
+        app.add_systems(OnEnter(GameState::Gameloop), spawn_hud);
+        app.add_systems(OnExit(GameState::Gameloop), despawn_hud);
+
+        app.add_systems(OnEnter(GameState::Connecting), spawn_connecting_screen);
+        app.add_systems(
+            Update,
+            connecting_screen_update.run_if(in_state(GameState::Connecting)),
         );
+        app.add_systems(OnExit(GameState::Connecting), despawn_connection_screen);
+
+        app.add_systems(OnEnter(GameState::Disconnected), spawn_disconnected_screen);
+        app.add_systems(OnExit(GameState::Disconnected), despawn_connection_screen);
     }
 }
@@ -1,7 +1,22 @@
-use bevy::prelude::{Children, Query};
+use bevy::prelude::*;
 use bevy_health_bar::ProgressBar;
 
-use crate::stats::components::Health;
+use crate::{
+    client::{
+        resources::{ClientDisconnectReason, ConnectionAttempt, CurrentClientId},
+        settings::ConnectionSettings,
+    },
+    deck::resources::ClientHand,
+    player::components::{Player, Team},
+    round::resources::{Contender, MatchPhase, MatchState},
+    score::resources::{ClientScoreboard, KillFeed},
+    stats::components::{Health, StatusEffects},
+};
+
+use super::components::{
+    ConnectionScreen, ConnectionStatus, HandText, Hud, KillFeedText, MatchStatusText,
+    ScoreboardPanel, ScoreboardText, StatusText,
+};
 
 pub fn health_bar_update(
     query: Query<(&Health, &Children)>,
@@ -15,3 +30,351 @@ pub fn health_bar_update(
         }
     }
 }
+
+pub fn spawn_connecting_screen(commands: Commands) {
+    spawn_connection_screen(commands, String::new());
+}
+
+pub fn spawn_disconnected_screen(
+    commands: Commands,
+    client_disconnect_reason: Res<ClientDisconnectReason>,
+) {
+    spawn_connection_screen(
+        commands,
+        format!(
+            "Disconnected\n{}\n\nPress Enter to reconnect",
+            **client_disconnect_reason
+        ),
+    );
+}
+
+fn spawn_connection_screen(mut commands: Commands, status: String) {
+    commands.spawn((Camera2dBundle::default(), ConnectionScreen));
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            ConnectionScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    status,
+                    TextStyle {
+                        font_size: 24.0,
+                        ..default()
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center),
+                ConnectionStatus,
+            ));
+        });
+}
+
+pub fn connecting_screen_update(
+    connection_settings: Res<ConnectionSettings>,
+    connection_attempt: Res<ConnectionAttempt>,
+    mut query: Query<&mut Text, With<ConnectionStatus>>,
+) {
+    let mut status = format!(
+        "Connecting to {} (attempt {}/{})",
+        connection_settings.server_addr,
+        connection_attempt.attempt.max(1),
+        connection_settings.max_attempts
+    );
+
+    if let (Some(retry), Some(error)) = (&connection_attempt.retry, &connection_attempt.last_error)
+    {
+        status.push_str(&format!(
+            "\n{}, retrying in {:.1}s",
+            error,
+            retry.remaining_secs()
+        ));
+    }
+
+    for mut text in &mut query {
+        text.sections[0].value = status.clone();
+    }
+}
+
+pub fn despawn_connection_screen(
+    mut commands: Commands,
+    query: Query<Entity, With<ConnectionScreen>>,
+) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn spawn_hud(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(12.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            Hud,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    String::new(),
+                    TextStyle {
+                        font_size: 22.0,
+                        ..default()
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center),
+                MatchStatusText,
+            ));
+        });
+
+    commands.spawn((
+        TextBundle::from_section(
+            String::new(),
+            TextStyle {
+                font_size: 18.0,
+                ..default()
+            },
+        )
+        .with_text_alignment(TextAlignment::Right)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            right: Val::Px(12.0),
+            ..default()
+        }),
+        KillFeedText,
+        Hud,
+    ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            String::new(),
+            TextStyle {
+                font_size: 18.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.0),
+            left: Val::Px(12.0),
+            ..default()
+        }),
+        HandText,
+        Hud,
+    ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            String::new(),
+            TextStyle {
+                font_size: 18.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(64.0),
+            left: Val::Px(12.0),
+            ..default()
+        }),
+        StatusText,
+        Hud,
+    ));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            ScoreboardPanel,
+            Hud,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(16.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.75).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            String::new(),
+                            TextStyle {
+                                font_size: 20.0,
+                                ..default()
+                            },
+                        ),
+                        ScoreboardText,
+                    ));
+                });
+        });
+}
+
+pub fn despawn_hud(mut commands: Commands, query: Query<Entity, With<Hud>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn kill_feed_update(kill_feed: Res<KillFeed>, mut query: Query<&mut Text, With<KillFeedText>>) {
+    if !kill_feed.is_changed() {
+        return;
+    }
+
+    let lines: Vec<&str> = kill_feed
+        .lines
+        .iter()
+        .map(|line| line.text.as_str())
+        .collect();
+
+    for mut text in &mut query {
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+pub fn hand_update(client_hand: Res<ClientHand>, mut query: Query<&mut Text, With<HandText>>) {
+    if !client_hand.is_changed() {
+        return;
+    }
+
+    let card_keys = ["Z", "X", "C", "V", "B"];
+    let cards: Vec<String> = client_hand
+        .cards
+        .iter()
+        .zip(card_keys)
+        .map(|(card, key)| format!("[{}] {}", key, card))
+        .collect();
+
+    for mut text in &mut query {
+        text.sections[0].value = format!(
+            "{}\nDeck {}  Discard {}",
+            cards.join("  "),
+            client_hand.deck,
+            client_hand.discard_pile
+        );
+    }
+}
+
+pub fn status_update(
+    client_id: Res<CurrentClientId>,
+    player_query: Query<(&Player, &StatusEffects)>,
+    mut query: Query<&mut Text, With<StatusText>>,
+) {
+    let effects: Vec<String> = player_query
+        .iter()
+        .find(|(player, _)| player.id.raw() == client_id.0)
+        .map(|(_, status_effects)| {
+            status_effects
+                .iter()
+                .map(|effect| match effect.stacks {
+                    1 => format!("{} {:.1}s", effect.kind, effect.remaining()),
+                    stacks => format!("{} x{} {:.1}s", effect.kind, stacks, effect.remaining()),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    for mut text in &mut query {
+        text.sections[0].value = effects.join("  ");
+    }
+}
+
+pub fn scoreboard_update(
+    keyboard_input: Res<Input<KeyCode>>,
+    client_scoreboard: Res<ClientScoreboard>,
+    mut panel_query: Query<&mut Visibility, With<ScoreboardPanel>>,
+    mut text_query: Query<&mut Text, With<ScoreboardText>>,
+) {
+    let shown = keyboard_input.pressed(KeyCode::Tab);
+    for mut visibility in &mut panel_query {
+        *visibility = if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    if !shown {
+        return;
+    }
+
+    let mut table = format!(
+        "{:<20}{:>6}{:>8}{:>9}{:>9}",
+        "Player", "Kills", "Deaths", "Assists", "Damage"
+    );
+    for entry in client_scoreboard.entries.iter() {
+        table.push_str(&format!(
+            "\n{:<20}{:>6}{:>8}{:>9}{:>9.0}",
+            entry.name,
+            entry.score.kills,
+            entry.score.deaths,
+            entry.score.assists,
+            entry.score.damage_dealt
+        ));
+    }
+
+    for mut text in &mut text_query {
+        text.sections[0].value = table.clone();
+    }
+}
+
+pub fn match_status_update(
+    match_state: Res<MatchState>,
+    client_scoreboard: Res<ClientScoreboard>,
+    mut query: Query<&mut Text, With<MatchStatusText>>,
+) {
+    let remaining = match_state.remaining().as_secs();
+    let countdown = format!("{}:{:02}", remaining / 60, remaining % 60);
+    let name = |contender: Contender| match contender {
+        Contender::Team(index) => Team::Index(index).to_string(),
+        Contender::Player(id) => client_scoreboard.name(id),
+    };
+
+    let status = match match_state.phase {
+        MatchPhase::Warmup => format!("Warmup {}", countdown),
+        MatchPhase::InProgress => format!("Round {} {}", match_state.round, countdown),
+        MatchPhase::RoundEnd {
+            winner: Some(winner),
+        } => format!("{} wins round {}", name(winner), match_state.round),
+        MatchPhase::RoundEnd { winner: None } => {
+            format!("Round {} is a draw", match_state.round)
+        }
+        MatchPhase::MatchEnd {
+            winner: Some(winner),
+        } => format!("{} wins the match", name(winner)),
+        MatchPhase::MatchEnd { winner: None } => "The match is a draw".to_string(),
+    };
+
+    for mut text in &mut query {
+        text.sections[0].value = status.clone();
+    }
+}
@@ -9,6 +9,10 @@ pub mod math;
 pub mod networking;
 pub mod physics;
 pub mod player;
+pub mod round;
+pub mod score;
 pub mod server;
+pub mod settings;
 pub mod stats;
+pub mod team;
 pub mod ui;